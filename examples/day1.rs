@@ -1,64 +1,587 @@
+use anyhow::{bail, ensure, Context};
+use aoc::{ranked::RankedMap, Day};
 use itertools::Itertools;
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BTreeMap, BinaryHeap, HashMap},
+    fmt::{self, Write},
+    io::BufRead,
+    sync::OnceLock,
+};
 
 const INPUT: &str = include_str!("inputs/day1.txt");
 
-fn _functional() -> u32 {
-    let group_strs = INPUT.split("\n\n");
-    let mut elves_in_groups = group_strs
-        .map(|group| {
-            // Convert group of strs into group of integers
-            group
-                .lines()
-                .map(|line| line.parse::<u32>().unwrap())
-                .collect_vec()
+/// What an elf carries
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Elf {
+    total: u64,
+    items: usize,
+}
+
+impl Elf {
+    fn mean(&self) -> f64 {
+        self.total as f64 / self.items as f64
+    }
+}
+
+/// Reads the inventory line by line and calls `visit` with the calories of
+/// each elf's items, elf by elf in order. Lines may end in `\r\n`, the last
+/// elf needs no blank line after it, and runs of blank lines don't make
+/// elves without food.
+fn read_elves(inventory: impl BufRead, mut visit: impl FnMut(&[u64])) -> anyhow::Result<()> {
+    let mut items = vec![];
+    for (idx, line) in inventory.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            if !items.is_empty() {
+                visit(&items);
+                items.clear();
+            }
+            continue;
+        }
+        let calories = line
+            .parse::<u64>()
+            .with_context(|| format!("line {}: expected calories, found {line:?}", idx + 1))?;
+        items.push(calories);
+    }
+    if !items.is_empty() {
+        visit(&items);
+    }
+    Ok(())
+}
+
+/// Returns the calories carried by the `k` elves carrying the most, most
+/// first. Only `k` sums are kept.
+fn top_k(inventory: impl BufRead, k: usize) -> anyhow::Result<Vec<u64>> {
+    // The smallest of the top sums is on top, to be replaced first
+    let mut top = BinaryHeap::with_capacity(k + 1);
+    read_elves(inventory, |items| {
+        top.push(Reverse(items.iter().sum::<u64>()));
+        if top.len() > k {
+            top.pop();
+        }
+    })?;
+
+    Ok(top
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(sum)| sum)
+        .collect())
+}
+
+/// The percentiles in the stats report
+const PERCENTILES: [u32; 5] = [10, 25, 50, 75, 90];
+/// The number of bins of the histogram in the stats report
+const BINS: usize = 10;
+
+/// A bin of the histogram of the calories the elves carry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Bin {
+    /// The least calories in the bin
+    from: u64,
+    /// The least calories past the bin
+    to: u64,
+    elves: usize,
+}
+
+/// The statistics of what the elves carry, for planning food logistics.
+/// Elves are numbered from 1 in input order.
+struct Inventory {
+    elves: Vec<Elf>,
+    /// The totals from least to most
+    totals: Vec<u64>,
+}
+
+impl Inventory {
+    fn new(elves: &[Vec<u64>]) -> Inventory {
+        let elves = elves
+            .iter()
+            .map(|items| Elf {
+                total: items.iter().sum(),
+                items: items.len(),
+            })
+            .collect::<Vec<_>>();
+        let mut totals = elves.iter().map(|elf| elf.total).collect::<Vec<_>>();
+        totals.sort_unstable();
+        Inventory { elves, totals }
+    }
+
+    /// Renders the stats as `text`, `csv` or `json`.
+    fn report(&self, format: &str) -> anyhow::Result<String> {
+        Ok(match format {
+            "text" => self.to_string(),
+            "csv" => self.csv(),
+            "json" => self.json(),
+            _ => bail!("unknown stats format: {format}, expected text, csv or json"),
         })
-        .collect_vec();
+    }
+
+    fn median(&self) -> Option<f64> {
+        let n = self.totals.len();
+        match n {
+            0 => None,
+            _ if n % 2 == 1 => Some(self.totals[n / 2] as f64),
+            _ => Some((self.totals[n / 2 - 1] + self.totals[n / 2]) as f64 / 2.0),
+        }
+    }
 
-    elves_in_groups.sort_by(|a, b| a.iter().sum::<u32>().cmp(&b.iter().sum::<u32>()));
+    /// Returns the least total that at least `p` percent of the elves carry
+    /// at most, by nearest rank.
+    fn percentile(&self, p: u32) -> Option<u64> {
+        let rank = (p as usize * self.totals.len()).div_ceil(100).max(1);
+        self.totals.get(rank - 1).copied()
+    }
 
-    let fattest = elves_in_groups.last().unwrap();
-    fattest.into_iter().sum::<u32>()
+    /// Splits the totals from the least to the most into `bins` bins of
+    /// equal width.
+    fn histogram(&self, bins: usize) -> Vec<Bin> {
+        let (Some(&min), Some(&max)) = (self.totals.first(), self.totals.last()) else {
+            return vec![];
+        };
+        let width = (max - min) / bins as u64 + 1;
+        let mut histogram = (0..bins as u64)
+            .map(|bin| Bin {
+                from: min + bin * width,
+                to: min + (bin + 1) * width,
+                elves: 0,
+            })
+            .collect::<Vec<_>>();
+        for total in &self.totals {
+            histogram[((total - min) / width) as usize].elves += 1;
+        }
+        histogram
+    }
+
+    /// Returns the totals carried by more than one elf, with the numbers of
+    /// those elves.
+    fn ties(&self) -> Vec<(u64, Vec<usize>)> {
+        let mut by_total = BTreeMap::<u64, Vec<usize>>::new();
+        for (idx, elf) in self.elves.iter().enumerate() {
+            by_total.entry(elf.total).or_default().push(idx + 1);
+        }
+        by_total
+            .into_iter()
+            .filter(|(_, elves)| elves.len() > 1)
+            .collect()
+    }
+
+    /// One row per elf
+    fn csv(&self) -> String {
+        let mut csv = String::from("elf,total,items,mean\n");
+        for (idx, elf) in self.elves.iter().enumerate() {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{}",
+                idx + 1,
+                elf.total,
+                elf.items,
+                elf.mean()
+            );
+        }
+        csv
+    }
+
+    fn json(&self) -> String {
+        let elves = self.elves.iter().enumerate().map(|(idx, elf)| {
+            format!(
+                r#"{{"elf":{},"total":{},"items":{},"mean":{}}}"#,
+                idx + 1,
+                elf.total,
+                elf.items,
+                elf.mean()
+            )
+        });
+        let percentiles = PERCENTILES.iter().map(|&p| {
+            let value = self.percentile(p);
+            format!(r#""p{p}":{}"#, json_or_null(value))
+        });
+        let histogram = self.histogram(BINS).into_iter().map(|bin| {
+            format!(
+                r#"{{"from":{},"to":{},"elves":{}}}"#,
+                bin.from, bin.to, bin.elves
+            )
+        });
+        let ties = self.ties().into_iter().map(|(total, elves)| {
+            format!(
+                r#"{{"total":{total},"elves":[{}]}}"#,
+                elves.iter().join(",")
+            )
+        });
+        format!(
+            r#"{{"elves":[{}],"median":{},"percentiles":{{{}}},"histogram":[{}],"ties":[{}]}}"#,
+            elves.format(","),
+            json_or_null(self.median()),
+            percentiles.format(","),
+            histogram.format(","),
+            ties.format(","),
+        )
+    }
 }
 
-fn main() -> anyhow::Result<()> {
-    let mut elves = vec![];
+fn json_or_null(value: Option<impl fmt::Display>) -> String {
+    value.map_or("null".to_string(), |value| value.to_string())
+}
 
-    let mut lines = INPUT.lines();
-    let mut elf = vec![];
-    while let Some(line) = lines.next() {
-        if line.is_empty() {
-            // Store this elf and start building another one
-            elves.push(elf);
-            elf = vec![];
-            continue;
+impl fmt::Display for Inventory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items = self.elves.iter().map(|elf| elf.items).sum::<usize>();
+        let calories = self.totals.iter().sum::<u64>();
+        writeln!(
+            f,
+            "{} elves carry {items} items, {calories} calories",
+            self.elves.len()
+        )?;
+        if let Some(median) = self.median() {
+            write!(f, "Median {median}")?;
+            for p in PERCENTILES {
+                write!(f, ", p{p} {}", json_or_null(self.percentile(p)))?;
+            }
+            writeln!(f)?;
         }
+        let most = self.histogram(BINS).iter().map(|bin| bin.elves).max();
+        for bin in self.histogram(BINS) {
+            // Bars of at most 40 columns
+            let bar = bin.elves * 40 / most.unwrap_or(1);
+            writeln!(
+                f,
+                "{:>8}..{:<8} {:>5} {}",
+                bin.from,
+                bin.to,
+                bin.elves,
+                "#".repeat(bar)
+            )?;
+        }
+        for (total, elves) in self.ties() {
+            writeln!(f, "Elves {} all carry {total}", elves.iter().join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The elves' inventories as they change, ranked by the calories they carry.
+/// Elves are numbered from 1 in the order they join.
+#[derive(Default)]
+struct Ledger {
+    /// The calories each elf carries
+    totals: Vec<u64>,
+    /// How many items of each size each elf carries
+    items: Vec<HashMap<u64, usize>>,
+    /// The elves from the one carrying the most, ties by number
+    ranked: RankedMap<(Reverse<u64>, usize)>,
+}
 
-        let num = line.parse::<u32>()?;
-        elf.push(num);
+impl Ledger {
+    fn new(elves: &[Vec<u64>]) -> Ledger {
+        let mut ledger = Ledger::default();
+        for items in elves {
+            let elf = ledger.add_elf();
+            for &calories in items {
+                ledger.add_item(elf, calories);
+            }
+        }
+        ledger
     }
 
-    elves.sort_by(|a, b| a.iter().sum::<u32>().cmp(&b.iter().sum::<u32>()));
+    fn add_elf(&mut self) -> usize {
+        self.totals.push(0);
+        self.items.push(HashMap::new());
+        let elf = self.totals.len();
+        self.ranked.insert((Reverse(0), elf), 0);
+        elf
+    }
 
-    part1(&mut elves);
-    part2(&mut elves);
+    /// Moves `elf` to its place for its new total, in O(log n).
+    fn update(&mut self, elf: usize, total: u64) {
+        let old = std::mem::replace(&mut self.totals[elf - 1], total);
+        self.ranked.remove(&(Reverse(old), elf));
+        self.ranked.insert((Reverse(total), elf), total);
+    }
 
-    Ok(())
+    fn add_item(&mut self, elf: usize, calories: u64) {
+        *self.items[elf - 1].entry(calories).or_default() += 1;
+        self.update(elf, self.totals[elf - 1] + calories);
+    }
+
+    /// Removes an item of `calories` from `elf`, returning whether it had
+    /// one, in O(log n).
+    fn remove_item(&mut self, elf: usize, calories: u64) -> bool {
+        let Entry::Occupied(mut count) = self.items[elf - 1].entry(calories) else {
+            return false;
+        };
+        *count.get_mut() -= 1;
+        if *count.get() == 0 {
+            count.remove();
+        }
+        self.update(elf, self.totals[elf - 1] - calories);
+        true
+    }
+
+    /// Returns the calories carried by the `k` elves carrying the most, in
+    /// O(log n).
+    fn top(&self, k: usize) -> u64 {
+        self.ranked.sum_first(k)
+    }
+
+    /// Returns the place of `elf`, 1 for the elf carrying the most, in
+    /// O(log n). Elves carrying as much are placed by number.
+    fn rank(&self, elf: usize) -> usize {
+        let key = (Reverse(self.totals[elf - 1]), elf);
+        self.ranked.rank(&key).expect("every elf is ranked") + 1
+    }
+
+    /// Applies comma separated edits, where `3+500` gives elf 3 an item of
+    /// 500 calories and `2-1000` takes one of 1000 calories from elf 2. An
+    /// elf number one past the last elf adds an elf. Returns the place of
+    /// each edited elf and the calories of the top 1 and top 3 afterwards.
+    fn edit(&mut self, edits: &str) -> anyhow::Result<String> {
+        let mut edited = vec![];
+        for edit in edits.split(',') {
+            let (elf, sign, calories) = edit
+                .find(['+', '-'])
+                .and_then(|idx| {
+                    let elf = edit[..idx].trim().parse::<usize>().ok()?;
+                    let calories = edit[idx + 1..].trim().parse::<u64>().ok()?;
+                    Some((elf, &edit[idx..idx + 1], calories))
+                })
+                .with_context(|| {
+                    format!("expected ELF+CALORIES or ELF-CALORIES, found {edit:?}")
+                })?;
+            if elf == self.totals.len() + 1 {
+                self.add_elf();
+            }
+            ensure!(
+                (1..=self.totals.len()).contains(&elf),
+                "there is no elf {elf}, the elves go from 1 to {}",
+                self.totals.len()
+            );
+            if sign == "+" {
+                self.add_item(elf, calories);
+            } else if !self.remove_item(elf, calories) {
+                bail!("elf {elf} has no item of {calories} calories");
+            }
+            if !edited.contains(&elf) {
+                edited.push(elf);
+            }
+        }
+
+        let mut report = String::new();
+        for elf in edited {
+            let _ = writeln!(
+                report,
+                "Elf {elf} carries {} calories, place {}",
+                self.totals[elf - 1],
+                self.rank(elf)
+            );
+        }
+        let _ = writeln!(report, "Top 1: {}, top 3: {}", self.top(1), self.top(3));
+        Ok(report)
+    }
+}
+
+/// The inventory as text, for the implementations that stream it and keep
+/// only what they need. The elves are parsed from it once, the first time an
+/// implementation that works on all of them asks.
+struct Elves {
+    text: String,
+    /// The calories of each elf's items, in input order
+    items: OnceLock<Vec<Vec<u64>>>,
+}
+
+impl Elves {
+    fn items(&self) -> anyhow::Result<&[Vec<u64>]> {
+        if let Some(items) = self.items.get() {
+            return Ok(items);
+        }
+        let mut items = vec![];
+        read_elves(self.text.as_bytes(), |elf| items.push(elf.to_vec()))?;
+        Ok(self.items.get_or_init(|| items))
+    }
 }
 
-fn part1(elves: &mut [Vec<u32>]) {
-    let fattest = elves.last().unwrap();
-    let n = fattest.into_iter().sum::<u32>();
+fn parse(input: &str) -> anyhow::Result<Elves> {
+    Ok(Elves {
+        text: input.to_string(),
+        items: OnceLock::new(),
+    })
+}
+
+fn streaming(elves: &Elves, k: usize) -> anyhow::Result<u64> {
+    Ok(top_k(elves.text.as_bytes(), k)?.iter().sum())
+}
+
+/// Reports the calories carried by each of the `k` elves carrying the most,
+/// and their sum, streaming the inventory.
+fn top(elves: &Elves, k: &str) -> anyhow::Result<String> {
+    let k = k
+        .parse()
+        .with_context(|| format!("invalid number of elves: {k}"))?;
+    let top = top_k(elves.text.as_bytes(), k)?;
+    let mut report = String::new();
+    for (place, calories) in top.iter().enumerate() {
+        let _ = writeln!(report, "{:>4}. {calories}", place + 1);
+    }
+    let _ = writeln!(report, "Total {}", top.iter().sum::<u64>());
+    Ok(report)
+}
 
-    println!("{n}");
+/// Sorts the elves by the calories they carry
+fn by_total(elves: &[Vec<u64>]) -> Vec<&Vec<u64>> {
+    let mut elves = elves.iter().collect::<Vec<_>>();
+    elves.sort_by_key(|elf| elf.iter().sum::<u64>());
+    elves
 }
 
-fn part2(elves: &mut [Vec<u32>]) {
-    let three_fattest = elves
+/// Finds the fattest elf without relying on the elves being sorted
+fn functional(elves: &[Vec<u64>]) -> anyhow::Result<u64> {
+    elves
+        .iter()
+        .map(|elf| elf.iter().sum::<u64>())
+        .max()
+        .context("there are no elves")
+}
+
+#[test]
+fn streams_any_layout() {
+    let streamed = |input: &str, k| top_k(input.as_bytes(), k).unwrap();
+    assert_eq!(streamed("1\n2\n\n4\n\n3", 2), [4, 3]);
+    assert_eq!(streamed("1\r\n2\r\n\r\n4\r\n", 1), [4]);
+    assert_eq!(streamed("\n\n1\n\n\n\n2\n\n", 5), [2, 1]);
+    assert_eq!(streamed("1\n\n2", 0), [] as [u64; 0]);
+    assert!(top_k("1\nx\n".as_bytes(), 1).is_err());
+
+    let elves = parse("1\n\n5\n\n3").unwrap();
+    assert_eq!(top(&elves, "2").unwrap(), "   1. 5\n   2. 3\nTotal 8\n");
+    assert!(top(&elves, "x").is_err());
+    // The elves are only parsed for the implementations that need them
+    assert!(elves.items.get().is_none());
+    assert_eq!(functional(elves.items().unwrap()).unwrap(), 5);
+
+    let empty = parse("\n\n").unwrap();
+    assert!(functional(empty.items().unwrap()).is_err());
+    assert!(part1(&by_total(empty.items().unwrap())).is_err());
+    assert!(parse("1\nx\n").unwrap().items().is_err());
+}
+
+#[test]
+fn reports_stats() {
+    let inventory = Inventory::new(
+        parse("1\n2\n\n3\n\n10\n\n3\n\n2\n2\n2")
+            .unwrap()
+            .items()
+            .unwrap(),
+    );
+    assert_eq!(inventory.median(), Some(3.0));
+    assert_eq!(inventory.percentile(10), Some(3));
+    assert_eq!(inventory.percentile(90), Some(10));
+    assert_eq!(inventory.ties(), [(3, vec![1, 2, 4])]);
+    let histogram = inventory.histogram(3);
+    assert_eq!(
+        histogram.iter().map(|bin| bin.elves).collect::<Vec<_>>(),
+        [3, 1, 1]
+    );
+    assert_eq!((histogram[0].from, histogram[2].to), (3, 12));
+
+    let csv = inventory.csv();
+    assert_eq!(csv.lines().nth(1), Some("1,3,2,1.5"));
+    assert_eq!(csv.lines().count(), 6);
+    let json = inventory.json();
+    assert!(json.starts_with(r#"{"elves":[{"elf":1,"total":3,"items":2,"mean":1.5},"#));
+    assert!(json.ends_with(r#""ties":[{"total":3,"elves":[1,2,4]}]}"#));
+
+    let empty = Inventory::new(parse("\n").unwrap().items().unwrap());
+    assert_eq!((empty.median(), empty.percentile(50)), (None, None));
+    assert!(inventory.report("xml").is_err());
+    assert!(empty
+        .json()
+        .contains(r#""median":null,"percentiles":{"p10":null"#));
+}
+
+#[test]
+fn ledger_tracks_changes() {
+    let mut ledger = Ledger::new(
+        parse("1\r\n2\r\n\r\n4\r\n\r\n3\r\n")
+            .unwrap()
+            .items()
+            .unwrap(),
+    );
+    assert_eq!((ledger.top(1), ledger.top(2), ledger.top(10)), (4, 7, 10));
+    assert_eq!([1, 2, 3].map(|elf| ledger.rank(elf)), [2, 1, 3]);
+
+    ledger.add_item(3, 5);
+    assert_eq!((ledger.top(1), ledger.rank(3)), (8, 1));
+    assert!(ledger.remove_item(1, 2));
+    assert!(!ledger.remove_item(1, 2));
+    assert!(!ledger.remove_item(3, 4));
+    assert_eq!([1, 2, 3].map(|elf| ledger.rank(elf)), [3, 2, 1]);
+
+    let elf = ledger.add_elf();
+    ledger.add_item(elf, 4);
+    // Ties go to the elf with the lower number
+    assert_eq!((ledger.rank(2), ledger.rank(elf)), (2, 3));
+    assert_eq!(ledger.top(3), 16);
+
+    let report = ledger.edit("2-4, 5+1,3-5").unwrap();
+    assert_eq!(
+        report.lines().collect::<Vec<_>>(),
+        [
+            "Elf 2 carries 0 calories, place 5",
+            "Elf 5 carries 1 calories, place 4",
+            "Elf 3 carries 3 calories, place 2",
+            "Top 1: 4, top 3: 8",
+        ]
+    );
+    assert!(ledger.edit("1-100").is_err());
+    assert!(ledger.edit("7+1").is_err());
+    assert!(ledger.edit("1*2").is_err());
+}
+
+fn main() -> anyhow::Result<()> {
+    Day::new(1, INPUT, parse)
+        .part1("streaming", |elves| streaming(elves, 1))
+        .part1("loop", |elves| part1(&by_total(elves.items()?)))
+        .part1("functional", |elves| functional(elves.items()?))
+        .part1("ledger", |elves| {
+            elves.items().map(|items| Ledger::new(items).top(1))
+        })
+        .part2("streaming", |elves| streaming(elves, 3))
+        .part2("loop", |elves| {
+            elves.items().map(|items| part2(&by_total(items)))
+        })
+        .part2("ledger", |elves| {
+            elves.items().map(|items| Ledger::new(items).top(3))
+        })
+        .mode(
+            "stats",
+            Some("FORMAT"),
+            "print the stats of the inventory as text, csv or json",
+            |elves, format| Inventory::new(elves.items()?).report(format.unwrap_or("text")),
+        )
+        .mode(
+            "ledger",
+            Some("EDITS"),
+            "add and remove items, like 3+500,2-1000, and print the places of the elves",
+            |elves, edits| Ledger::new(elves.items()?).edit(edits.unwrap_or_default()),
+        )
+        .mode(
+            "top",
+            Some("K"),
+            "print the calories carried by the K elves carrying the most, streaming the inventory",
+            |elves, k| top(elves, k.unwrap_or_default()),
+        )
+        .main()
+}
+
+fn part1(elves: &[&Vec<u64>]) -> anyhow::Result<u64> {
+    let fattest = elves.last().context("there are no elves")?;
+    Ok(fattest.iter().sum::<u64>())
+}
+
+fn part2(elves: &[&Vec<u64>]) -> u64 {
+    elves
         .iter()
         .rev()
         .take(3)
-        .map(|x| x.iter().sum::<u32>())
-        .sum::<u32>();
-
-    println!("{three_fattest}");
+        .map(|x| x.iter().sum::<u64>())
+        .sum::<u64>()
 }
@@ -1,3 +1,6 @@
+use aoc::Day;
+use itertools::Itertools;
+
 const INPUT: &str = include_str!("inputs/day10.txt");
 
 fn do_cycle(cycle: &mut i32, reg: i32) -> Option<i32> {
@@ -11,13 +14,21 @@ fn do_cycle(cycle: &mut i32, reg: i32) -> Option<i32> {
     ret
 }
 
-fn part1() -> i32 {
+fn parse(input: &str) -> anyhow::Result<Vec<String>> {
+    let extras = "noop\nnoop\nnoop\n";
+    Ok(input
+        .lines()
+        .chain(extras.lines())
+        .map(String::from)
+        .collect())
+}
+
+fn part1(lines: &[String]) -> i32 {
     let mut reg = 1;
     let mut cycle = 1;
     let mut sum = 0;
-    let extras = "noop\nnoop\nnoop\n";
 
-    for line in INPUT.lines().chain(extras.lines()) {
+    for line in lines {
         let mut toks = line.split_ascii_whitespace();
         let first_tok = toks.next().unwrap();
 
@@ -54,13 +65,12 @@ fn do_cycle_p2(cycle: &mut i32, reg: i32, screen: &mut Vec<Vec<bool>>) {
     *cycle += 1;
 }
 
-fn main() -> anyhow::Result<()> {
+fn part2(lines: &[String]) -> anyhow::Result<String> {
     let mut reg = 1;
     let mut cycle = 1;
-    let extras = "noop\nnoop\nnoop\n";
     let mut screen = vec![vec![false; 40]; 6];
 
-    for line in INPUT.lines().chain(extras.lines()) {
+    for line in lines {
         let mut toks = line.split_ascii_whitespace();
         let first_tok = toks.next().unwrap();
 
@@ -78,15 +88,25 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
-    println!("Part 1: {}", part1());
-    println!("Part 2:");
-    for line in screen {
-        println!(
-            "{}",
+    Ok(screen
+        .into_iter()
+        .map(|line| {
             line.into_iter()
                 .map(|b| if b { '#' } else { ' ' })
                 .collect::<String>()
-        )
-    }
-    Ok(())
+        })
+        .join("\n"))
+}
+
+#[test]
+fn crt_screen_snapshot() {
+    let lines = parse(include_str!("inputs/day10-example.txt")).unwrap();
+    aoc::assert_snapshot!("day10-example-screen", part2(&lines).unwrap());
+}
+
+fn main() -> anyhow::Result<()> {
+    Day::new(10, INPUT, parse)
+        .part1("cycles", |lines| part1(lines))
+        .part2("crt", |lines| part2(lines))
+        .main()
 }
@@ -1,149 +1,139 @@
+use anyhow::ensure;
+use aoc::{
+    num::{self, Int, Overflow},
+    parse::{blocks, parse_all, preceded, tag, uint, Parser},
+    Day,
+};
 use itertools::Itertools;
-use std::{num::ParseIntError, str::FromStr};
+use std::rc::Rc;
 
 const INPUT: &str = include_str!("inputs/day11.txt");
-impl std::str::FromStr for Monkey {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-
-        // Skip heading "Monkey X"
-        lines.next().unwrap();
-
-        let items = lines
-            .next()
-            .unwrap()
-            .split_once(':')
-            .unwrap()
-            .1
-            .split(',')
-            .map(|tok| tok.trim().parse::<usize>().unwrap())
-            .collect_vec();
-
-        let op_str = lines.next().unwrap().split_once(':').unwrap().1.trim();
-        let (operator, param) = {
-            let (_, rhs) = op_str.split_once('=').unwrap();
-            let mut toks = rhs.trim().split_ascii_whitespace();
-
-            // Skip 'old'
-            let old_str = toks.next().unwrap();
-            assert_eq!(old_str, "old");
-
-            let operator = toks.next().unwrap().chars().next().unwrap();
-            let param = match toks.next().unwrap() {
-                "old" => None,
-                num => Some(num.parse::<usize>().unwrap()),
-            };
-            (operator, param)
-        };
-        let op = move |old: usize| {
-            let param = match param {
-                Some(n) => n,
-                None => old,
-            };
-            match operator {
-                '*' => old * param,
-                '+' => old + param,
-                _ => panic!("unknown op: {}", operator),
-            }
-        };
-        let op = Box::new(op);
-
-        let test_var = lines
-            .next()
-            .unwrap()
-            .split_ascii_whitespace()
-            .last()
-            .unwrap()
-            .parse::<usize>()?;
-        let next_if_true = lines
-            .next()
-            .unwrap()
-            .split_ascii_whitespace()
-            .last()
-            .unwrap()
-            .parse::<usize>()?;
-        let next_if_false = lines
-            .next()
-            .unwrap()
-            .split_ascii_whitespace()
-            .last()
-            .unwrap()
-            .parse::<usize>()?;
-
-        Ok(Monkey {
-            items,
-            op,
-            test_var,
-            next_if_true,
-            next_if_false,
-        })
-    }
+/// Parses a monkey like
+///
+/// ```text
+/// Monkey 0:
+///   Starting items: 79, 98
+///   Operation: new = old * 19
+///   Test: divisible by 23
+///     If true: throw to monkey 2
+///     If false: throw to monkey 3
+/// ```
+fn monkey<'a>() -> impl Parser<'a, Monkey> {
+    let heading = tag("Monkey ").then(uint::<usize>()).then(tag(":\n"));
+    let items = preceded(tag("  Starting items: "), uint().sep_by(tag(", ")));
+    let operator = tag("*").or(tag("+")).map(|op| op == "*");
+    let param = tag("old").map(|_| None).or(uint().map(Some));
+    let operation = preceded(tag("\n  Operation: new = old "), operator)
+        .skip(tag(" "))
+        .then(param);
+    let test_var = preceded(tag("\n  Test: divisible by "), uint());
+    let next_if_true = preceded(tag("\n    If true: throw to monkey "), uint());
+    let next_if_false = preceded(tag("\n    If false: throw to monkey "), uint());
+
+    preceded(heading, items)
+        .then(operation)
+        .then(test_var)
+        .then(next_if_true)
+        .then(next_if_false)
+        .map(
+            |((((items, (multiply, param)), test_var), next_if_true), next_if_false)| {
+                let op = move |old: Int| {
+                    let param = match param {
+                        Some(n) => n,
+                        None => old,
+                    };
+                    if multiply {
+                        num::mul(old, param)
+                    } else {
+                        num::add(old, param)
+                    }
+                };
+                Monkey {
+                    items,
+                    op: Rc::new(op),
+                    test_var,
+                    next_if_true,
+                    next_if_false,
+                }
+            },
+        )
 }
 
+#[derive(Clone)]
 struct Monkey {
-    items: Vec<usize>,
-    op: Box<dyn Fn(usize) -> usize>,
-    test_var: usize,
+    items: Vec<Int>,
+    op: Rc<dyn Fn(Int) -> Result<Int, Overflow>>,
+    test_var: Int,
     next_if_true: usize,
     next_if_false: usize,
 }
 
 impl Monkey {
     // Returns (where, what)
-    fn take_turn(&mut self, relief: bool, common_denom: usize) -> Vec<(usize, usize)> {
+    fn take_turn(
+        &mut self,
+        relief: bool,
+        common_denom: Int,
+    ) -> Result<Vec<(usize, Int)>, Overflow> {
         let mut thrown = Vec::with_capacity(self.items.len());
         let items = self.items.clone();
         self.items.clear();
         for item in items {
-            thrown.push(self.inspect(item, relief, common_denom));
+            thrown.push(self.inspect(item, relief, common_denom)?);
         }
-        thrown
+        Ok(thrown)
     }
 
     // Returns thrown (where, what)
-    fn inspect(&self, item: usize, relief: bool, common_denom: usize) -> (usize, usize) {
-        let item = (self.op)(item);
+    fn inspect(
+        &self,
+        item: Int,
+        relief: bool,
+        common_denom: Int,
+    ) -> Result<(usize, Int), Overflow> {
+        let item = (self.op)(item)?;
 
         // Part 1 only
         let item = if relief {
-            item / 3
+            num::div(item, 3)?
         } else {
-            item % common_denom
+            num::rem(item, common_denom)?
         };
 
-        let next = if (item % self.test_var) == 0 {
+        let next = if num::rem(item, self.test_var)? == 0 {
             self.next_if_true
         } else {
             self.next_if_false
         };
-        (next, item)
+        Ok((next, item))
     }
 }
 
-fn round(monkeys: &mut [Monkey], relief: bool, divs: &[usize]) -> [usize; 8] {
-    let mut inspections = [0; 8];
+/// The number of monkeys the rounds are sized for
+const MONKEYS: usize = 8;
+
+fn round(monkeys: &mut [Monkey], relief: bool, divs: &[Int]) -> Result<[usize; MONKEYS], Overflow> {
+    let mut inspections = [0; MONKEYS];
 
-    let common_denom: usize = divs.iter().product();
-    for idx in 0..8 {
+    let common_denom = divs.iter().try_fold(1, |acc, &div| num::mul(acc, div))?;
+    for idx in 0..MONKEYS {
         let monkey = &mut monkeys[idx];
-        let thrown = monkey.take_turn(relief, common_denom);
+        let thrown = monkey.take_turn(relief, common_denom)?;
         inspections[idx] += thrown.len() as usize;
         for (next, item) in thrown {
             monkeys[next].items.push(item);
         }
     }
 
-    inspections
+    Ok(inspections)
 }
 
-fn part1(mut monkeys: Vec<Monkey>) -> usize {
+fn part1(mut monkeys: Vec<Monkey>) -> Result<usize, Overflow> {
     let monkeys = monkeys.as_mut_slice();
 
-    let mut inspections = vec![0; 8];
+    let mut inspections = vec![0; MONKEYS];
     for _ in 0..20 {
-        let this_round = round(monkeys, true, &[]);
+        let this_round = round(monkeys, true, &[])?;
         this_round
             .iter()
             .zip(inspections.iter_mut())
@@ -156,17 +146,17 @@ fn part1(mut monkeys: Vec<Monkey>) -> usize {
     let two_most_active = &inspections[0..2];
 
     // Monkey business
-    two_most_active[0] * two_most_active[1]
+    Ok(two_most_active[0] * two_most_active[1])
 }
 
-fn part2(mut monkeys: Vec<Monkey>) -> usize {
+fn part2(mut monkeys: Vec<Monkey>) -> Result<usize, Overflow> {
     let divisors = monkeys.iter().map(|m| m.test_var).collect_vec();
 
     let monkeys = monkeys.as_mut_slice();
 
-    let mut inspections = vec![0; 8];
+    let mut inspections = vec![0; MONKEYS];
     for _rnd in 0..10_000 {
-        let this_round = round(monkeys, false, &divisors);
+        let this_round = round(monkeys, false, &divisors)?;
         this_round
             .iter()
             .zip(inspections.iter_mut())
@@ -179,16 +169,31 @@ fn part2(mut monkeys: Vec<Monkey>) -> usize {
     let two_most_active = &inspections[0..2];
 
     // Monkey business
-    two_most_active[0] * two_most_active[1]
+    Ok(two_most_active[0] * two_most_active[1])
+}
+
+fn parse(input: &str) -> anyhow::Result<Vec<Monkey>> {
+    Ok(parse_all(blocks(monkey()), input)?)
 }
 
 fn main() -> anyhow::Result<()> {
-    let monkeys = INPUT
-        .split("\n\n")
-        .map(|text| Monkey::from_str(text).unwrap())
-        .collect_vec();
-
-    //println!("Part 1: {}", part1(monkeys));
-    println!("Part 2: {}", part2(monkeys));
-    Ok(())
+    Day::new(11, INPUT, parse)
+        .assume("there are 8 monkeys", |monkeys| {
+            ensure!(monkeys.len() == MONKEYS, "found {} monkeys", monkeys.len());
+            Ok(())
+        })
+        .assume("monkeys throw to other monkeys", |monkeys| {
+            for (idx, monkey) in monkeys.iter().enumerate() {
+                for next in [monkey.next_if_true, monkey.next_if_false] {
+                    ensure!(
+                        next < monkeys.len() && next != idx,
+                        "monkey {idx} throws to monkey {next}"
+                    );
+                }
+            }
+            Ok(())
+        })
+        .part1("rounds", |monkeys| part1(monkeys.clone()))
+        .part2("rounds", |monkeys| part2(monkeys.clone()))
+        .main()
 }
@@ -1,10 +1,15 @@
 use std::collections::{HashSet, VecDeque};
 
+use anyhow::Context;
+use aoc::{
+    parse::{grid, parse_all},
+    Day,
+};
 use itertools::Itertools;
 
 const INPUT: &str = include_str!("inputs/day12.txt");
 
-fn available_cardinals(hmap: &Vec<Vec<usize>>, this: (usize, usize)) -> Vec<(usize, usize)> {
+fn available_cardinals(hmap: &[Vec<usize>], this: (usize, usize)) -> Vec<(usize, usize)> {
     let mut av = vec![];
     let curh = hmap[this.1][this.0];
     if this.0 != 0 && hmap[this.1][this.0 - 1] <= curh + 1 {
@@ -23,7 +28,7 @@ fn available_cardinals(hmap: &Vec<Vec<usize>>, this: (usize, usize)) -> Vec<(usi
 }
 
 fn shortest_path_bfs(
-    hmap: &Vec<Vec<usize>>,
+    hmap: &[Vec<usize>],
     start: (usize, usize),
     end: (usize, usize),
 ) -> Option<Vec<(usize, usize)>> {
@@ -54,38 +59,43 @@ fn shortest_path_bfs(
     None
 }
 
-fn main() -> anyhow::Result<()> {
-    let mut start = (0, 0);
-    let mut end = (0, 0);
-    let hmap: Vec<Vec<usize>> = INPUT
-        .lines()
-        .enumerate()
-        .map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(x, c)| {
-                    let c = match c {
-                        'S' => {
-                            start = (x, y);
-                            'a'
-                        }
-                        'E' => {
-                            end = (x, y);
-                            'z'
-                        }
-                        c => c,
-                    };
-                    c as usize - 97
+type Coord = (usize, usize);
+
+fn parse(input: &str) -> anyhow::Result<(Vec<Vec<usize>>, Coord, Coord)> {
+    let cells = grid("a height, 'S' or 'E'", |c| {
+        matches!(c, 'a'..='z' | 'S' | 'E').then_some(c)
+    });
+    let cells = parse_all(cells, input)?;
+    let find = |mark| {
+        cells
+            .iter()
+            .enumerate()
+            .find_map(|(y, row)| Some((row.iter().position(|&c| c == mark)?, y)))
+            .with_context(|| format!("no {mark:?} in the map"))
+    };
+    let start = find('S')?;
+    let end = find('E')?;
+    let hmap = cells
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&c| match c {
+                    'S' => 0,
+                    'E' => 25,
+                    c => c as usize - 97,
                 })
                 .collect_vec()
         })
         .collect();
 
-    println!(
-        "Part 1: {}",
-        shortest_path_bfs(&hmap, start, end).unwrap().len() - 1
-    );
+    Ok((hmap, start, end))
+}
+
+fn part1(hmap: &[Vec<usize>], start: Coord, end: Coord) -> usize {
+    shortest_path_bfs(hmap, start, end).unwrap().len() - 1
+}
 
+fn part2(hmap: &[Vec<usize>], end: Coord) -> usize {
     let mut relevant_starts = Vec::new();
 
     for (y, line) in hmap.iter().enumerate() {
@@ -100,10 +110,15 @@ fn main() -> anyhow::Result<()> {
 
     let shortest_paths = relevant_starts
         .iter()
-        .filter_map(|start| shortest_path_bfs(&hmap, *start, end).map(|x| x.len() - 1))
+        .filter_map(|start| shortest_path_bfs(hmap, *start, end).map(|x| x.len() - 1))
         .collect_vec();
 
-    println!("Part 2: {}", *shortest_paths.iter().min().unwrap());
+    *shortest_paths.iter().min().unwrap()
+}
 
-    Ok(())
+fn main() -> anyhow::Result<()> {
+    Day::new(12, INPUT, parse)
+        .part1("bfs", |(hmap, start, end)| part1(hmap, *start, *end))
+        .part2("bfs", |(hmap, _, end)| part2(hmap, *end))
+        .main()
 }
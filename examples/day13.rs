@@ -1,5 +1,9 @@
+use aoc::{
+    parse::{blocks, parse_all, preceded, tag, uint, Input, PResult, Parser},
+    Day,
+};
 use itertools::Itertools;
-use std::{cmp::Ordering, convert::Infallible, str::FromStr};
+use std::cmp::Ordering;
 
 const INPUT: &str = include_str!("inputs/day13.txt");
 
@@ -49,98 +53,20 @@ impl std::cmp::Ord for Data {
     }
 }
 
-impl std::str::FromStr for Data {
-    type Err = Infallible;
-
-    fn from_str(s: &str) -> Result<Data, Self::Err> {
-        // Input like "[1,[2,[3,[4,[5,6,7]]]],8,9]"
-
-        struct Parser {
-            depth: usize,
-            head: Vec<Data>,
-        }
-
-        impl Parser {
-            fn tokenize(s: &str) -> Vec<String> {
-                let mut ret = vec![];
-                let mut cur = String::new();
-                for c in s.chars() {
-                    if ['[', ']', ','].contains(&c) {
-                        if !cur.is_empty() {
-                            ret.push(cur.clone());
-                            cur.clear();
-                        }
-                        ret.push(c.to_string());
-                    } else {
-                        cur.push(c);
-                    }
-                }
-                ret
-            }
-
-            fn get_tail_mut(&mut self) -> &mut Vec<Data> {
-                let mut tail = &mut self.head;
-                for _ in 0..self.depth {
-                    tail = match tail.last_mut().unwrap() {
-                        Data::List(l) => l,
-                        Data::Int(_) => panic!("cannot push list into int"),
-                    };
-                }
-                tail
-            }
-            fn push_list(&mut self, list: Vec<Data>) {
-                self.get_tail_mut().push(Data::List(list));
-                self.depth += 1;
-            }
-            fn pop_list(&mut self) {
-                self.depth -= 1;
-            }
-            fn push_int(&mut self, i: u32) {
-                self.get_tail_mut().push(Data::Int(i));
-            }
-
-            fn parse(s: &str) -> Data {
-                let mut p = Parser {
-                    depth: 0,
-                    head: vec![],
-                };
-
-                let toks = Self::tokenize(s);
-                for tok in toks {
-                    match tok.as_str() {
-                        "[" => {
-                            p.push_list(vec![]);
-                        }
-                        "]" => {
-                            p.pop_list();
-                        }
-                        "," => {}
-                        numchar => {
-                            p.push_int(numchar.parse::<u32>().unwrap());
-                        }
-                    }
-                }
-                let x = p.head.drain(..).next().unwrap();
-                x
-            }
-        }
-
-        Ok(Parser::parse(s))
-    }
+/// Parses a packet like `[1,[2,[3,[4,[5,6,7]]]],8,9]`.
+fn data(input: Input) -> PResult<Data> {
+    let list = preceded(tag("["), data.sep_by(tag(",")).opt())
+        .skip(tag("]"))
+        .map(|items| Data::List(items.unwrap_or_default()));
+    list.or(uint().map(Data::Int)).parse(input)
 }
 
-fn part1() -> usize {
-    let pairs = INPUT
-        .split("\n\n")
-        .map(|pair| {
-            let (first, second) = pair.split_once("\n").expect("malformed input");
-            (
-                Data::from_str(first).unwrap(),
-                Data::from_str(second).unwrap(),
-            )
-        })
-        .collect_vec();
+fn parse(input: &str) -> anyhow::Result<Vec<(Data, Data)>> {
+    let pair = data.skip(tag("\n")).then(data);
+    Ok(parse_all(blocks(pair), input)?)
+}
 
+fn part1(pairs: &[(Data, Data)]) -> usize {
     let indices = pairs.iter().enumerate().filter_map(|(idx, (a, b))| {
         if a.cmp(b) != Ordering::Greater {
             Some(idx + 1)
@@ -152,11 +78,10 @@ fn part1() -> usize {
     indices.sum::<usize>()
 }
 
-fn part2() -> usize {
-    let mut data = INPUT
-        .lines()
-        .filter(|line| !line.is_empty())
-        .map(|line| Data::from_str(line).unwrap())
+fn part2(pairs: &[(Data, Data)]) -> usize {
+    let mut data = pairs
+        .iter()
+        .flat_map(|(a, b)| [a.clone(), b.clone()])
         .collect_vec();
     let dividers = vec![
         Data::List(vec![Data::List(vec![Data::Int(2)])]),
@@ -176,7 +101,8 @@ fn part2() -> usize {
 }
 
 fn main() -> anyhow::Result<()> {
-    println!("Part 1: {}", part1());
-    println!("Part 2: {}", part2());
-    Ok(())
+    Day::new(13, INPUT, parse)
+        .part1("compare", |pairs| part1(pairs))
+        .part2("sort", |pairs| part2(pairs))
+        .main()
 }
@@ -1,3 +1,4 @@
+use aoc::{sparse::SparseGrid, Day};
 use itertools::Itertools;
 
 const INPUT: &str = include_str!("inputs/day14.txt");
@@ -112,8 +113,42 @@ fn part2(mut walls: Vec<Wall>, bottom: isize) -> usize {
     sand_count
 }
 
-fn main() -> anyhow::Result<()> {
-    let walls = INPUT
+/// Pours sand onto the walls drawn in a sparse grid until it falls past
+/// `bottom`, or, if there is a `floor` at `bottom`, until it piles up to the
+/// spawn point. Returns how many units came to rest.
+fn pour(walls: &[Wall], bottom: isize, floor: bool) -> usize {
+    let mut grid = SparseGrid::new();
+    for Wall(start, end) in walls {
+        for x in start.x()..=end.x() {
+            for y in start.y()..=end.y() {
+                grid.insert((x, y));
+            }
+        }
+    }
+    let walls = grid.len();
+
+    let spawn = (500, 0);
+    while !grid.contains(spawn) {
+        let (mut x, mut y) = spawn;
+        loop {
+            if y >= bottom {
+                return grid.len() - walls;
+            }
+            let next = [(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)]
+                .into_iter()
+                .find(|&pos| !grid.contains(pos) && (!floor || pos.1 != bottom));
+            match next {
+                Some(pos) => (x, y) = pos,
+                None => break,
+            }
+        }
+        grid.insert((x, y));
+    }
+    grid.len() - walls
+}
+
+fn parse(input: &str) -> anyhow::Result<(Vec<Wall>, isize)> {
+    let walls = input
         .lines()
         .map(|line| {
             line.split("->")
@@ -141,7 +176,14 @@ fn main() -> anyhow::Result<()> {
         .unwrap()
         + 2;
 
-    println!("Part 1: {}", part1(walls.clone(), bottom));
-    println!("Part 2: {}", part2(walls.clone(), bottom));
-    Ok(())
+    Ok((walls, bottom))
+}
+
+fn main() -> anyhow::Result<()> {
+    Day::new(14, INPUT, parse)
+        .part1("sparse grid", |(walls, bottom)| pour(walls, *bottom, false))
+        .part1("walls", |(walls, bottom)| part1(walls.clone(), *bottom))
+        .part2("sparse grid", |(walls, bottom)| pour(walls, *bottom, true))
+        .part2("walls", |(walls, bottom)| part2(walls.clone(), *bottom))
+        .main()
 }
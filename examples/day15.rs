@@ -1,4 +1,7 @@
-use itertools::Itertools;
+use aoc::{
+    parse::{ints_in, lines, parse_all, Parser},
+    Day,
+};
 
 const INPUT: &str = include_str!("inputs/day15.txt");
 
@@ -42,9 +45,12 @@ fn part1(balls: &[Ball]) -> usize {
         .count()
 }
 
-fn part2(balls: &[Ball]) -> i64 {
+fn part2(balls: &[Ball]) -> anyhow::Result<i64> {
     let mut y = 0;
     while y <= 4000000 {
+        if y % 1024 == 0 {
+            aoc::cancel::check(|| format!("{y} of 4000001 rows scanned"))?;
+        }
         let mut x = 0;
         'x: while x <= 4000000 {
             for ball in balls {
@@ -55,59 +61,30 @@ fn part2(balls: &[Ball]) -> i64 {
                     continue 'x;
                 }
             }
-            return x as i64 * 4000000 + y as i64;
+            return Ok(x as i64 * 4000000 + y as i64);
         }
         y += 1;
     }
-    panic!("no beacon found")
+    anyhow::bail!("no beacon found")
 }
 
-fn main() -> anyhow::Result<()> {
-    let inputs = INPUT.lines().map(|line| {
-        let toks = line.split_ascii_whitespace();
-        let mut parsing_beacon = false;
-        let (mut sx, mut sy, mut bx, mut by) = (None, None, None, None);
-        for tok in toks {
-            let first = tok.chars().nth(0).unwrap();
-            match first {
-                c @ 'x' | c @ 'y' => {
-                    let rhs: String = tok
-                        .split_once('=')
-                        .unwrap()
-                        .1
-                        .chars()
-                        .filter(|c| c.is_numeric() || *c == '-')
-                        .collect();
-                    let num = rhs.parse::<isize>().unwrap();
-                    match (c, parsing_beacon) {
-                        ('x', true) => bx = Some(num),
-                        ('x', false) => sx = Some(num),
-                        ('y', true) => by = Some(num),
-                        ('y', false) => {
-                            parsing_beacon = true;
-                            sy = Some(num);
-                        }
-                        _ => unreachable!(),
-                    }
-                }
-                _ => {}
-            }
-        }
-        ((sx.unwrap(), sy.unwrap()), (bx.unwrap(), by.unwrap()))
-    });
-
-    let balls = inputs
-        .map(|(sens, beac)| {
-            let dist = dist(sens, beac);
-            Ball {
-                pos: sens,
-                rad: dist,
-            }
+fn parse(input: &str) -> anyhow::Result<Vec<Ball>> {
+    // Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+    let ball = ints_in().try_map("a sensor and a beacon", |nums: Vec<isize>| {
+        let [sx, sy, bx, by] = nums[..] else {
+            return None;
+        };
+        Some(Ball {
+            pos: (sx, sy),
+            rad: dist((sx, sy), (bx, by)),
         })
-        .collect_vec();
-
-    println!("Part 1: {}", part1(&balls));
-    println!("Part 2: {}", part2(&balls));
+    });
+    Ok(parse_all(lines(ball), input)?)
+}
 
-    Ok(())
+fn main() -> anyhow::Result<()> {
+    Day::new(15, INPUT, parse)
+        .part1("scan", |balls| part1(balls))
+        .part2("skip", |balls| part2(balls))
+        .main()
 }
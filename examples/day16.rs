@@ -1,8 +1,15 @@
+use aoc::bitset::Bits64;
+use aoc::memo::Memo;
+use aoc::search::Search;
+use aoc::Day;
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 const INPUT: &str = include_str!("inputs/day16.txt");
 
+/// Upper bound for the number of cached strategies
+const MEMO_CAPACITY: usize = 1 << 20;
+
 type Id = [char; 2];
 
 fn str_to_id(s: &str) -> Id {
@@ -14,129 +21,228 @@ fn _id_to_string(id: &Id) -> String {
     [id[0], id[1]].iter().collect()
 }
 
+/// Gives each valve with a non-zero flow a bit in the set of opened valves
+type ValveBits = HashMap<Id, usize>;
+
+fn valve_bits(nz_wgts: &HashMap<Id, usize>) -> ValveBits {
+    let mut ids = nz_wgts.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+    assert!(ids.len() <= Bits64::CAPACITY, "too many valves");
+    ids.into_iter()
+        .enumerate()
+        .map(|(bit, id)| (id, bit))
+        .collect()
+}
+
+type StrategyKey = (Id, usize, Bits64);
+type StrategyMemo = Memo<StrategyKey, (usize, Option<Id>)>;
+
 /// Returns the best strategy available when starting at this position, with
-/// `min_rem` minutes remaining.
+/// `time_rem` minutes remaining. The strategy is given as the pressure it
+/// releases and the node it moves to first, if it moves at all.
 fn best_strategy(
     pos: &Id,
     time_rem: usize,
     jumps: &HashMap<Id, Vec<Id>>,
     nz_wgts: &HashMap<Id, usize>,
-    opened: HashSet<Id>,
-    mut curpath: Vec<Id>,
-) -> (usize, Vec<Id>) {
-    curpath.push(*pos);
-
-    // If there is no time left, there are no more strategies left
-    if time_rem == 0 {
-        return (0, curpath);
-    }
+    bits: &ValveBits,
+    opened: Bits64,
+    memo: &mut StrategyMemo,
+) -> (usize, Option<Id>) {
+    let key = (*pos, time_rem, opened);
+    memo.get_or_compute(key, |memo| {
+        // If there is no time left, there are no more strategies left
+        if time_rem == 0 {
+            return (0, None);
+        }
 
-    // All non-zero valves open, no further actions can add value
-    if opened.len() == nz_wgts.len() {
-        return (0, curpath);
-    }
+        // All non-zero valves open, no further actions can add value
+        if opened.len() == nz_wgts.len() {
+            return (0, None);
+        }
 
-    let mut all_strats = vec![];
+        let mut all_strats = vec![];
 
-    let wgt = nz_wgts.get(pos).unwrap_or(&0);
+        let wgt = nz_wgts.get(pos).unwrap_or(&0);
+        let bit = bits.get(pos).copied();
 
-    // If this is not already open, the strategy of just opening this one is
-    // available
-    let mut open_this = 0;
-    if !opened.contains(pos) {
-        open_this = (time_rem - 1) * wgt;
-        all_strats.push((open_this, curpath.clone()));
-    };
+        // If this is not already open, the strategy of just opening this one is
+        // available
+        let mut open_this = 0;
+        if bit.is_some_and(|bit| !opened.contains(bit)) {
+            open_this = (time_rem - 1) * wgt;
+            all_strats.push((open_this, None));
+        };
 
-    // Add strategies in subsequent nodes
-    for dest in &jumps[pos] {
-        // Strategies where we revisit nodes do not make sense
-        if curpath.contains(dest) {
-            continue;
-        }
+        // Add strategies in subsequent nodes. Revisiting a node is allowed
+        // because the memo makes the repeated states cheap.
+        for dest in &jumps[pos] {
+            // Strats for **not** opening this node (-0) + moving through tunnel (-1)
+            let (dont_open, _) =
+                best_strategy(dest, time_rem - 1, jumps, nz_wgts, bits, opened, memo);
+            all_strats.push((dont_open, Some(*dest)));
 
-        let mut opened = opened.clone();
-
-        // Strats for **not** opening this node (-0) + moving through tunnel (-1)
-        let dont_open = best_strategy(
-            dest,
-            time_rem - 1,
-            jumps,
-            nz_wgts,
-            opened.clone(),
-            curpath.clone(),
-        );
-        all_strats.push(dont_open);
-
-        // Strats for opening this node (-1) + moving through tunnel (-1)
-        if open_this != 0 && time_rem > 2 {
-            opened.insert(*pos);
-            let strat = best_strategy(dest, time_rem - 2, jumps, nz_wgts, opened, curpath.clone());
-            let open_val = open_this + strat.0;
-            all_strats.push((open_val, strat.1));
+            // Strats for opening this node (-1) + moving through tunnel (-1)
+            if let Some(bit) = bit.filter(|_| open_this != 0 && time_rem > 2) {
+                let mut opened = opened;
+                opened.insert(bit);
+                let (strat, _) =
+                    best_strategy(dest, time_rem - 2, jumps, nz_wgts, bits, opened, memo);
+                let open_val = open_this + strat;
+                all_strats.push((open_val, Some(*dest)));
+            }
         }
-    }
-
-    // Identify the best strategy available at this position with current time
-    let best_here = all_strats.iter().max().unwrap_or(&(0, curpath)).clone();
 
-    best_here
+        // Identify the best strategy available at this position with current time
+        all_strats.into_iter().max().unwrap_or((0, None))
+    })
 }
 
 fn part1(jumps: &HashMap<Id, Vec<Id>>, nz_wgts: &HashMap<Id, usize>) -> usize {
     let curpos = str_to_id("AA");
-    best_strategy(&curpos, 30, &jumps, &nz_wgts, HashSet::new(), vec![]).0
+    let mut memo = Memo::with_capacity_limit(MEMO_CAPACITY);
+    let bits = valve_bits(nz_wgts);
+    let released = best_strategy(&curpos, 30, jumps, nz_wgts, &bits, Bits64::new(), &mut memo).0;
+    aoc::phase::note("memo", memo.stats());
+    released
+}
+
+/// A state of the branch and bound search: where we are, the minutes left,
+/// the opened valves and the pressure they release until the end
+#[derive(Clone, Copy)]
+struct Walk {
+    pos: Id,
+    time_rem: usize,
+    opened: Bits64,
+    released: usize,
+}
+
+fn branch_and_bound(jumps: &Jumps, nz_wgts: &Flows, time: usize) -> anyhow::Result<usize> {
+    let bits = valve_bits(nz_wgts);
+    let mut flows = nz_wgts
+        .iter()
+        .map(|(id, wgt)| (bits[id], *wgt))
+        .collect::<Vec<_>>();
+    flows.sort_by_key(|&(_, wgt)| std::cmp::Reverse(wgt));
+
+    let branch = |walk: &Walk, moves: &mut Vec<Walk>| {
+        if walk.time_rem == 0 {
+            return;
+        }
+        let time_rem = walk.time_rem - 1;
+        // Opening the valve here first, as it tends to lead to better walks
+        if let Some(&bit) = bits
+            .get(&walk.pos)
+            .filter(|&&bit| !walk.opened.contains(bit))
+        {
+            let mut opened = walk.opened;
+            opened.insert(bit);
+            moves.push(Walk {
+                time_rem,
+                opened,
+                released: walk.released + time_rem * nz_wgts[&walk.pos],
+                ..*walk
+            });
+        }
+        for dest in &jumps[&walk.pos] {
+            moves.push(Walk {
+                pos: *dest,
+                time_rem,
+                ..*walk
+            });
+        }
+    };
+    // At best the closed valves are opened from the largest down, one every
+    // other minute starting with the next
+    let bound = |walk: &Walk| {
+        let closed = flows.iter().filter(|(bit, _)| !walk.opened.contains(*bit));
+        let minutes = (1..walk.time_rem).rev().step_by(2);
+        walk.released
+            + closed
+                .zip(minutes)
+                .map(|((_, wgt), t)| wgt * t)
+                .sum::<usize>()
+    };
+    // Being in the same place with the same valves open sooner and having
+    // released more is never worse
+    let search = Search::new(branch, |walk: &Walk| walk.released, bound).dominance(
+        |walk| (walk.pos, walk.opened),
+        |a, b| a.time_rem >= b.time_rem && a.released >= b.released,
+    );
+
+    let outcome = search.run(Walk {
+        pos: str_to_id("AA"),
+        time_rem: time,
+        opened: Bits64::new(),
+        released: 0,
+    });
+    aoc::phase::note("search", outcome.stats);
+    if outcome.cancelled {
+        aoc::cancel::check(|| format!("{} released at best so far", outcome.value))?;
+    }
+    Ok(outcome.value)
 }
 
 fn do_greedy_next(
     curpos: &Id,
     time_rem: usize,
-    opened: &mut HashSet<Id>,
+    opened: &mut Bits64,
     jumps: &HashMap<Id, Vec<Id>>,
     nz_wgts: &HashMap<Id, usize>,
+    bits: &ValveBits,
+    memo: &mut StrategyMemo,
 ) -> (Id, usize) {
     // Find the strategy that is currently the best
-    let best = best_strategy(curpos, time_rem, jumps, nz_wgts, opened.clone(), vec![]).1;
+    let (_, next) = best_strategy(curpos, time_rem, jumps, nz_wgts, bits, *opened, memo);
 
     // If there is a valve here, open it
     if let Some(wgt) = nz_wgts.get(curpos) {
-        opened.insert(*curpos);
+        opened.insert(bits[curpos]);
         let pressure = (time_rem - 1) * wgt;
         return (*curpos, pressure);
     } else {
         // If the next step is somewhere else, we change position
-        return (*best.get(1).unwrap_or(curpos), 0);
+        return (next.unwrap_or(*curpos), 0);
     }
 }
 
-/// Greedy
-fn part2(jumps: &HashMap<Id, Vec<Id>>, nz_wgts: &HashMap<Id, usize>) -> usize {
+/// Greedy: every actor takes the currently best step in turn
+fn greedy(
+    jumps: &HashMap<Id, Vec<Id>>,
+    nz_wgts: &HashMap<Id, usize>,
+    actors: usize,
+    time: usize,
+) -> usize {
     let mut released = 0;
 
-    let mut mypos = str_to_id("AA");
-    let mut elepos = str_to_id("AA");
-    let mut time_rem = 24;
-    let mut opened = HashSet::new();
-
-    while time_rem != 0 {
-        println!("Time remaining: {time_rem}");
-        let mynext = do_greedy_next(&mypos, time_rem, &mut opened, jumps, nz_wgts);
-        mypos = mynext.0;
-        released += mynext.1;
-
-        let elenext = do_greedy_next(&elepos, time_rem, &mut opened, jumps, nz_wgts);
-        elepos = elenext.0;
-        released += elenext.1;
+    let mut positions = vec![str_to_id("AA"); actors];
+    let mut opened = Bits64::new();
+    let bits = valve_bits(nz_wgts);
+    let mut memo = Memo::with_capacity_limit(MEMO_CAPACITY);
 
-        time_rem -= 1;
+    for time_rem in (1..=time).rev() {
+        for pos in positions.iter_mut() {
+            let (next, pressure) =
+                do_greedy_next(pos, time_rem, &mut opened, jumps, nz_wgts, &bits, &mut memo);
+            *pos = next;
+            released += pressure;
+        }
     }
+    aoc::phase::note("memo", memo.stats());
     released
 }
 
-fn main() -> anyhow::Result<()> {
+fn part2(jumps: &HashMap<Id, Vec<Id>>, nz_wgts: &HashMap<Id, usize>) -> usize {
+    greedy(jumps, nz_wgts, 2, 24)
+}
+
+type Jumps = HashMap<Id, Vec<Id>>;
+type Flows = HashMap<Id, usize>;
+
+fn parse(input: &str) -> anyhow::Result<(Jumps, Flows)> {
     let valve_re = Regex::new(r"([A-Z]{2})").unwrap();
     let flow_re = Regex::new(r"(\d+)").unwrap();
-    let edges = INPUT
+    let edges = input
         .lines()
         .map(|line| {
             let mut valves = valve_re.captures_iter(line);
@@ -169,7 +275,15 @@ fn main() -> anyhow::Result<()> {
         njumps
     };
 
-    println!("Part 1: {}", part1(&jumps, &flows));
-    println!("Part 2: {}", part2(&jumps, &flows));
-    Ok(())
+    Ok((jumps, flows))
+}
+
+fn main() -> anyhow::Result<()> {
+    Day::new(16, INPUT, parse)
+        .part1("branch and bound", |(jumps, flows)| {
+            branch_and_bound(jumps, flows, 30)
+        })
+        .part1("exhaustive", |(jumps, flows)| part1(jumps, flows))
+        .part2("greedy", |(jumps, flows)| part2(jumps, flows))
+        .main()
 }
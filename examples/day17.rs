@@ -1,3 +1,8 @@
+use aoc::{
+    checkpoint::{Checkpointer, Record},
+    parse::{parse_all, take_while1, Parser},
+    Day,
+};
 use itertools::Itertools;
 use std::{
     collections::HashMap,
@@ -314,7 +319,7 @@ impl Block {
         let ny = y - 1;
 
         // If there is no collision, update y
-        if !skip_coll || !self.as_coords_at_pos((x, ny)).any(|co| map[co.1][co.0]) {
+        if skip_coll || !self.as_coords_at_pos((x, ny)).any(|co| map[co.1][co.0]) {
             self.pos.1 = ny;
             false
         }
@@ -325,28 +330,36 @@ impl Block {
     }
 }
 
-fn draw_map(draw_map: &Vec<Vec<bool>>) {
+#[cfg_attr(not(test), allow(dead_code))]
+fn render_map(draw_map: &[Vec<bool>]) -> String {
+    let mut out = String::new();
     for line in draw_map.iter().rev() {
-        print!("|");
+        out.push('|');
         for x in line {
-            if *x {
-                print!("#");
-            } else {
-                print!(" ");
-            }
+            out.push(if *x { '#' } else { ' ' });
         }
-        print!("|");
-        println!();
+        out.push_str("|\n");
     }
-    println!("+-------+");
+    out.push_str("+-------+\n");
+    out
 }
 
-fn draw_blocks(blocks: &[Block]) {
-    let mut v = vec![vec![false; 7]; 10];
+fn draw_map(draw_map: &[Vec<bool>]) {
+    print!("{}", render_map(draw_map));
+}
+
+/// Renders the blocks into a map that is `height` rows tall
+#[cfg_attr(not(test), allow(dead_code))]
+fn render_blocks(blocks: &[Block], height: usize) -> String {
+    let mut v = vec![vec![false; 7]; height];
     for block in blocks.iter() {
         block.draw(&mut v);
     }
-    draw_map(&v);
+    render_map(&v)
+}
+
+fn draw_blocks(blocks: &[Block]) {
+    print!("{}", render_blocks(blocks, 10));
 }
 
 fn any_collision(point: &Pos, blocks: &[Block]) -> bool {
@@ -354,15 +367,25 @@ fn any_collision(point: &Pos, blocks: &[Block]) -> bool {
 }
 
 fn part1(
+    push_dirs: impl Iterator<Item = bool>,
+    block_order: impl Iterator<Item = BlockKind>,
+) -> usize {
+    const NUM_BLOCKS: usize = 2022usize;
+    drop_blocks(push_dirs, block_order, NUM_BLOCKS).1
+}
+
+/// Drops `num_blocks` blocks with full collision checks. Returns the blocks
+/// at rest and the height of the tower.
+fn drop_blocks(
     mut push_dirs: impl Iterator<Item = bool>,
     mut block_order: impl Iterator<Item = BlockKind>,
-) -> usize {
+    num_blocks: usize,
+) -> (Vec<Block>, usize) {
     let mut blocks = vec![];
 
     let mut height = 0;
     let mut floor = 0;
-    const NUM_BLOCKS: usize = 2022usize;
-    for _ in 0..NUM_BLOCKS {
+    for _ in 0..num_blocks {
         let mut block = Block::new((2, height + 3).into(), block_order.next().unwrap());
         while let Some(push_dir) = push_dirs.next() {
             if block.tick_with_full_collision(push_dir, &blocks, 0) {
@@ -381,7 +404,7 @@ fn part1(
             .unwrap_or(floor)
             .max(floor);
     }
-    height
+    (blocks, height)
 }
 
 fn freeze(block_map: &mut Vec<[bool; 7]>, block: &Block) {
@@ -390,91 +413,296 @@ fn freeze(block_map: &mut Vec<[bool; 7]>, block: &Block) {
     }
 }
 
-fn part2(
-    mut push_dirs: impl Iterator<Item = bool>,
-    mut block_order: impl Iterator<Item = BlockKind>,
-) -> usize {
-    let mut block_map = vec![[false; WIDTH]; 1024];
+fn part2(push_dirs: &[bool], block_order: &[BlockKind]) -> anyhow::Result<usize> {
+    const NUM_BLOCKS: usize = 1_000_000_000_000usize;
+    drop_blocks_bitmap(push_dirs, block_order, NUM_BLOCKS)
+}
 
-    let mut height = 0;
-    let mut floor = 0;
-    let mut score = 0;
+/// A tower on a bitmap that is truncated at every full row
+#[derive(Clone, Debug, PartialEq)]
+struct BitmapTower {
+    block_map: Vec<[bool; WIDTH]>,
+    height: usize,
+    floor: usize,
+    /// Height of the rows truncated off the bottom of the map
+    score: usize,
+    /// Number of blocks dropped so far
+    round: usize,
+    /// Number of jets consumed so far
+    pushes: usize,
+}
+
+impl BitmapTower {
+    fn new() -> Self {
+        BitmapTower {
+            block_map: vec![[false; WIDTH]; 1024],
+            height: 0,
+            floor: 0,
+            score: 0,
+            round: 0,
+            pushes: 0,
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.score + self.height
+    }
+
+    fn drop_block(&mut self, push_dirs: &[bool], block_order: &[BlockKind]) {
+        let kind = block_order[self.round % block_order.len()];
+        let mut block = Block::new((2, self.height + 3).into(), kind);
+
+        loop {
+            let push_dir = push_dirs[self.pushes % push_dirs.len()];
+            self.pushes += 1;
+            if block.tick_optimized(push_dir, &self.block_map, block.pos.1 > self.height) {
+                break;
+            }
+        }
+        // Update height at end of round
+        if block.above() > self.height {
+            self.height = block.above();
+        }
+        freeze(&mut self.block_map, &block);
+
+        // Make room above the tower if necessary
+        if block.above() + 10 > self.block_map.len() {
+            // Cut the map at the highest full row under the block
+            let len = self.block_map.len();
+            if let Some(y) = (0..block.pos.1)
+                .rev()
+                .find(|&y| self.block_map[y].iter().all(|b| *b))
+            {
+                self.floor = y;
+                self.height -= y;
+                self.score += y;
+                self.block_map.drain(..y);
+                self.block_map.resize(len, [false; WIDTH]);
+            }
+            // Without a full row to cut at, the map has to grow
+            if self.height + 10 > self.block_map.len() {
+                self.block_map.resize(len * 2, [false; WIDTH]);
+            }
+        }
+        self.round += 1;
+    }
+
+    fn to_record(&self) -> Record {
+        // Two hex digits per row, bit x set if column x is filled
+        let map = self
+            .block_map
+            .iter()
+            .map(|row| {
+                let bits = (0..WIDTH).fold(0u8, |bits, x| bits | (row[x] as u8) << x);
+                format!("{bits:02x}")
+            })
+            .collect::<String>();
+        Record::new()
+            .with("map", map)
+            .with("height", self.height)
+            .with("floor", self.floor)
+            .with("score", self.score)
+            .with("round", self.round)
+            .with("pushes", self.pushes)
+    }
+
+    fn from_record(record: &Record) -> anyhow::Result<Self> {
+        let map = record.get::<String>("map")?;
+        let block_map = (0..map.len())
+            .step_by(2)
+            .map(|i| {
+                let bits = u8::from_str_radix(map.get(i..i + 2).unwrap_or_default(), 16)?;
+                let mut row = [false; WIDTH];
+                for (x, filled) in row.iter_mut().enumerate() {
+                    *filled = bits & (1 << x) != 0;
+                }
+                Ok(row)
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(BitmapTower {
+            block_map,
+            height: record.get("height")?,
+            floor: record.get("floor")?,
+            score: record.get("score")?,
+            round: record.get("round")?,
+            pushes: record.get("pushes")?,
+        })
+    }
+}
 
-    let mut pr = 0;
+/// Drops blocks on a [`BitmapTower`], returns the tower height. Saves and
+/// resumes from a checkpoint if the runner installed one.
+fn drop_blocks_bitmap(
+    push_dirs: &[bool],
+    block_order: &[BlockKind],
+    num_blocks: usize,
+) -> anyhow::Result<usize> {
+    let checkpoint = aoc::checkpoint::current();
+    let mut tower = BitmapTower::new();
+    if let Some(record) = checkpoint
+        .as_ref()
+        .map(Checkpointer::load)
+        .transpose()?
+        .flatten()
+    {
+        // The checkpoint must come from the same input and the same run
+        if record.get::<usize>("blocks")? != num_blocks
+            || record.get::<usize>("jets")? != push_dirs.len()
+        {
+            anyhow::bail!("checkpoint is for a different run");
+        }
+        tower = BitmapTower::from_record(&record)?;
+    }
+
+    let mut pr = tower.round;
     let mut pt = SystemTime::now();
-    const NUM_BLOCKS: usize = 1_000_000_000_000usize;
-    for round in 0..NUM_BLOCKS {
+    while tower.round < num_blocks {
+        let round = tower.round;
+        if round.is_multiple_of(1 << 16) {
+            if let Some(checkpoint) = checkpoint.as_ref().filter(|c| c.due()) {
+                let record = tower
+                    .to_record()
+                    .with("blocks", num_blocks)
+                    .with("jets", push_dirs.len());
+                checkpoint.save(&record)?;
+            }
+            aoc::cancel::check(|| {
+                format!(
+                    "{round} of {num_blocks} blocks dropped, height {}",
+                    tower.height()
+                )
+            })?;
+        }
+
         let since_last_measure = SystemTime::now().duration_since(pt).unwrap();
         if since_last_measure >= Duration::from_secs(1) {
             let rps = round - pr;
             println!(
                 "Blocks per second: {} ({} seconds remaining)",
                 rps,
-                ((NUM_BLOCKS - round) / rps)
+                ((num_blocks - round) / rps)
             );
             pr = round;
             pt = SystemTime::now();
         }
 
-        let mut block = Block::new((2, height + 3).into(), block_order.next().unwrap());
+        tower.drop_block(push_dirs, block_order);
+    }
 
-        while let Some(push_dir) = push_dirs.next() {
-            if block.tick_optimized(push_dir, &block_map, block.pos.1 > height) {
-                break;
-            }
-        }
-        // Update height at end of round
-        if block.above() > height {
-            height = block.above();
-        }
-        freeze(&mut block_map, &block);
-
-        // Extend the block map if necessary
-        if block.above() + 10 > block_map.len() {
-            // Find a new floor
-            for y in (0..block.pos.1).rev() {
-                if block_map[y].iter().all(|b| *b) {
-                    floor = y;
-                    height -= y;
-                    score += y;
-                    break;
-                }
-            }
-            let nmap = block_map[floor..].iter().chain(
-                std::iter::once(&[false; WIDTH])
-                    .cycle()
-                    .take(block_map.len() - floor),
-            );
-            block_map = nmap.cloned().collect();
-        }
+    if let Some(checkpoint) = &checkpoint {
+        checkpoint.finish()?;
     }
-    score + height
+    Ok(tower.height())
 }
 
-fn main() -> anyhow::Result<()> {
-    let push_dirs = INPUT
-        .chars()
-        .filter(|c| ['>', '<'].contains(c))
-        .map(|push| push == '>')
-        .cycle();
-    let block_order = [
-        BlockKind::Dash,
-        BlockKind::Plus,
-        BlockKind::J,
-        BlockKind::I,
-        BlockKind::Square,
-    ]
-    .iter()
-    .cycle();
-
-    /*println!(
-        "Part 1: {}",
-        part1(push_dirs.clone(), block_order.clone().cloned())
-    );*/
-    println!(
-        "Part 2: {}",
-        part2(push_dirs.clone(), block_order.clone().cloned())
+fn parse(input: &str) -> anyhow::Result<Vec<bool>> {
+    let jets = take_while1("jets", |c| c == '<' || c == '>');
+    let jets = jets.map(|jets| jets.chars().map(|push| push == '>').collect());
+    Ok(parse_all(jets, input)?)
+}
+
+const BLOCK_ORDER: [BlockKind; 5] = [
+    BlockKind::Dash,
+    BlockKind::Plus,
+    BlockKind::J,
+    BlockKind::I,
+    BlockKind::Square,
+];
+
+#[test]
+fn tower_snapshot() {
+    let push_dirs = parse(include_str!("inputs/day17-example.txt")).unwrap();
+    let (blocks, height) = drop_blocks(
+        push_dirs.iter().copied().cycle(),
+        BLOCK_ORDER.iter().copied().cycle(),
+        10,
+    );
+    aoc::assert_snapshot!("day17-example-tower", render_blocks(&blocks, height));
+}
+
+#[test]
+fn bitmap_matches_full_collision() {
+    let push_dirs = parse(include_str!("inputs/day17-example.txt")).unwrap();
+    let full = part1(
+        push_dirs.iter().copied().cycle(),
+        BLOCK_ORDER.iter().copied().cycle(),
     );
+    assert_eq!(full, 3068);
+    let bitmap = drop_blocks_bitmap(&push_dirs, &BLOCK_ORDER, 2022).unwrap();
+    assert_eq!(bitmap, full);
+}
+
+#[test]
+fn part2_stops_at_budget() {
+    use aoc::cancel::{self, Token};
 
-    Ok(())
+    let push_dirs = parse(include_str!("inputs/day17-example.txt")).unwrap();
+    let token = Token::with_budget(std::time::Duration::from_millis(100));
+    let err = cancel::scoped(&token, || part2(&push_dirs, &BLOCK_ORDER)).unwrap_err();
+    let err = err.downcast::<cancel::TimedOut>().unwrap();
+    assert!(err.progress.contains("blocks dropped"), "{err}");
+}
+
+#[test]
+fn bitmap_resumes_from_checkpoint() {
+    use aoc::{
+        cancel::{self, Token},
+        checkpoint,
+    };
+
+    const NUM_BLOCKS: usize = 200_000;
+    let push_dirs = parse(include_str!("inputs/day17-example.txt")).unwrap();
+    let uninterrupted = drop_blocks_bitmap(&push_dirs, &BLOCK_ORDER, NUM_BLOCKS).unwrap();
+
+    let path = std::env::temp_dir().join(format!("day17-checkpoint-{}.txt", std::process::id()));
+    let checkpointer = Checkpointer::new(&path, std::time::Duration::ZERO);
+
+    // A cancelled run leaves its state behind
+    let token = Token::new();
+    token.cancel();
+    let stopped = cancel::scoped(&token, || {
+        checkpoint::scoped(Some(&checkpointer), || {
+            drop_blocks_bitmap(&push_dirs, &BLOCK_ORDER, NUM_BLOCKS)
+        })
+    });
+    assert!(stopped.is_err());
+    let record = checkpointer.load().unwrap().unwrap();
+    assert_eq!(
+        BitmapTower::from_record(&record).unwrap(),
+        BitmapTower::new()
+    );
+
+    // Resume from a tower that is partly built
+    let mut tower = BitmapTower::new();
+    for _ in 0..70_000 {
+        tower.drop_block(&push_dirs, &BLOCK_ORDER);
+    }
+    let record = tower
+        .to_record()
+        .with("blocks", NUM_BLOCKS)
+        .with("jets", push_dirs.len());
+    assert_eq!(BitmapTower::from_record(&record).unwrap(), tower);
+    checkpointer.save(&record).unwrap();
+
+    let resumed = checkpoint::scoped(Some(&checkpointer), || {
+        drop_blocks_bitmap(&push_dirs, &BLOCK_ORDER, NUM_BLOCKS)
+    })
+    .unwrap();
+    assert_eq!(resumed, uninterrupted);
+    assert!(!path.exists());
+}
+
+fn main() -> anyhow::Result<()> {
+    Day::new(17, INPUT, parse)
+        .part1("full collision", |push_dirs| {
+            part1(
+                push_dirs.iter().copied().cycle(),
+                BLOCK_ORDER.iter().copied().cycle(),
+            )
+        })
+        .part1("bitmap", |push_dirs| {
+            drop_blocks_bitmap(push_dirs, &BLOCK_ORDER, 2022)
+        })
+        .part2("bitmap", |push_dirs| part2(push_dirs, &BLOCK_ORDER))
+        .main()
 }
@@ -1,3 +1,5 @@
+use anyhow::ensure;
+use aoc::Day;
 use itertools::Itertools;
 use std::collections::HashSet;
 
@@ -87,23 +89,31 @@ fn dfs_exterior_points(
     v
 }
 
-fn main() -> anyhow::Result<()> {
-    let mut points = vec![];
-    let mut volume = vec![vec![vec![false; XLEN]; YLEN]; ZLEN];
+type Point = (usize, usize, usize);
+type Volume = Vec<Vec<Vec<bool>>>;
 
-    for line in INPUT.lines() {
+fn parse(input: &str) -> anyhow::Result<Vec<Point>> {
+    let mut points = vec![];
+    for line in input.lines() {
         let mut toks = line.split(',');
         let x = toks.next().unwrap().parse::<usize>()?;
         let y = toks.next().unwrap().parse::<usize>()?;
         let z = toks.next().unwrap().parse::<usize>()?;
 
-        volume[z][y][x] = true;
         points.push((x, y, z));
     }
+    Ok(points)
+}
 
-    let sum = part1(points.clone(), &volume);
-    println!("Part 1: {sum}");
+fn volume(points: &[Point]) -> Volume {
+    let mut volume = vec![vec![vec![false; XLEN]; YLEN]; ZLEN];
+    for &(x, y, z) in points {
+        volume[z][y][x] = true;
+    }
+    volume
+}
 
+fn part2(points: Vec<Point>, volume: &Volume) -> usize {
     let mut face_points = vec![];
     for z in 0..ZLEN {
         for y in 0..YLEN {
@@ -119,7 +129,7 @@ fn main() -> anyhow::Result<()> {
     let mut visited = HashSet::new();
     let exterior_points = face_points
         .into_iter()
-        .flat_map(|(x, y, z)| dfs_exterior_points(x, y, z, &volume, &mut visited))
+        .flat_map(|(x, y, z)| dfs_exterior_points(x, y, z, volume, &mut visited))
         .collect_vec();
 
     let mut exterior_space = vec![vec![vec![false; XLEN]; YLEN]; ZLEN];
@@ -137,8 +147,23 @@ fn main() -> anyhow::Result<()> {
     }
 
     // Use part 1 solution to calculate surface area of filled topology
-    let sum = part1(points, &exterior_space);
-    println!("Part 2: {sum}");
+    part1(points, &exterior_space)
+}
 
-    Ok(())
+fn main() -> anyhow::Result<()> {
+    Day::new(18, INPUT, parse)
+        .assume("the cubes fit in 22×22×21", |points| {
+            for &(x, y, z) in points {
+                ensure!(
+                    x < XLEN && y < YLEN && z < ZLEN,
+                    "cube {x},{y},{z} is outside"
+                );
+            }
+            Ok(())
+        })
+        .part1("faces", |points| part1(points.clone(), &volume(points)))
+        .part2("flood fill", |points| {
+            part2(points.clone(), &volume(points))
+        })
+        .main()
 }
@@ -1,9 +1,12 @@
-use itertools::Itertools;
-use regex::Regex;
+use aoc::{
+    checkpoint::{Checkpointer, Record},
+    memo::Memo,
+    parse::{lines, parse_all, preceded, tag, uint, word, Parser},
+    search::Search,
+    Day,
+};
 use std::{
     collections::HashMap,
-    convert::Infallible,
-    str::FromStr,
     time::{Duration, SystemTime},
 };
 
@@ -17,18 +20,14 @@ enum Resource {
     Geode,
 }
 
-impl FromStr for Resource {
-    type Err = Infallible;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "ore" => Resource::Ore,
-            "clay" => Resource::Clay,
-            "obsidian" => Resource::Obsidian,
-            "geode" => Resource::Geode,
-            s => panic!("cannot parse Resource from \"{}\"", s),
-        })
-    }
+fn resource<'a>() -> impl Parser<'a, Resource> {
+    word().try_map("a resource", |s| match s {
+        "ore" => Some(Resource::Ore),
+        "clay" => Some(Resource::Clay),
+        "obsidian" => Some(Resource::Obsidian),
+        "geode" => Some(Resource::Geode),
+        _ => None,
+    })
 }
 
 #[derive(Default, Debug, Clone)]
@@ -69,44 +68,38 @@ impl Blueprint {
     fn geode_bot(&self) -> &Costs {
         &self.1[&Resource::Geode]
     }
+
+    /// Returns the cost of each bot in each resource, in the order of
+    /// `RESOURCES`
+    fn cost_matrix(&self) -> [[usize; 4]; 4] {
+        RESOURCES.map(|bot| {
+            let mut costs = [0; 4];
+            for (res, cost) in &self.1[&bot].0 {
+                costs[*res as usize] += cost;
+            }
+            costs
+        })
+    }
 }
 
 impl Blueprint {
-    fn parse(s: &str) -> anyhow::Result<Blueprint> {
-        let (id_str, cost_sents) = s.split_once(':').unwrap();
-
-        let bot_re = Regex::new(r"Each (\w+) robot")?;
-        let cost_re = Regex::new(r"(\d+) ([a-z]+)")?;
-
-        let bot_costs = cost_sents[..cost_sents.len() - 1]
-            .split('.')
-            .map(|cost_sent| {
-                let bot_kind_caps = bot_re.captures(cost_sent).unwrap();
-                let bot_kind = Resource::from_str(&bot_kind_caps[1]).unwrap();
-                let costs = cost_re
-                    .captures_iter(cost_sent)
-                    .map(|caps| {
-                        let num = &caps[1];
-                        let res = &caps[2];
-                        (
-                            Resource::from_str(res).unwrap(),
-                            num.parse::<usize>().unwrap(),
-                        )
-                    })
-                    .collect_vec();
-                (bot_kind, costs.into())
-            })
-            .collect_vec();
-
-        let id = id_str
-            .split_ascii_whitespace()
-            .skip(1)
-            .next()
-            .unwrap()
-            .parse::<usize>()
-            .unwrap();
-        let bp = Blueprint::from_bot_costs(id, bot_costs);
-        Ok(bp)
+    /// Parses a blueprint like
+    ///
+    /// ```text
+    /// Blueprint 1: Each ore robot costs 4 ore. Each obsidian robot costs 3 ore and 14 clay.
+    /// ```
+    fn parser<'a>() -> impl Parser<'a, Blueprint> {
+        let id = preceded(tag("Blueprint "), uint()).skip(tag(": "));
+        let cost = uint()
+            .skip(tag(" "))
+            .then(resource())
+            .map(|(n, res)| (res, n));
+        let bot = preceded(tag("Each "), resource())
+            .skip(tag(" robot costs "))
+            .then(cost.sep_by(tag(" and ")).map(Costs::from))
+            .skip(tag("."));
+        id.then(bot.sep_by(tag(" ")))
+            .map(|(id, bot_costs)| Blueprint::from_bot_costs(id, bot_costs))
     }
 }
 
@@ -147,13 +140,58 @@ fn triangular(n: usize) -> usize {
 static mut TURNS_SIMULATED: usize = 0;
 static mut STRATEGIES_SIMULATED: usize = 0;
 
+/// Upper bound for the number of cached game states per blueprint
+const MEMO_CAPACITY: usize = 1 << 22;
+
+const RESOURCES: [Resource; 4] = [
+    Resource::Ore,
+    Resource::Clay,
+    Resource::Obsidian,
+    Resource::Geode,
+];
+
+/// A game state before `choice` is paid for: the bot to build, the time left
+/// and the resource and bot counts in the order of `RESOURCES`
+type StateKey = (Option<Resource>, usize, [usize; 4], [usize; 4]);
+
+fn state_key(
+    choice: &Option<(Resource, Costs)>,
+    time: usize,
+    resources: &HashMap<Resource, usize>,
+    bots: &HashMap<Resource, usize>,
+) -> StateKey {
+    (
+        choice.as_ref().map(|(bot, _)| *bot),
+        time,
+        RESOURCES.map(|res| resources[&res]),
+        RESOURCES.map(|res| bots[&res]),
+    )
+}
+
+/// Returns the most geodes that can be opened after making `choice` with
+/// `time` minutes left.
 fn simulate_dfs(
+    choice: Option<(Resource, Costs)>,
+    time: usize,
+    resources: HashMap<Resource, usize>,
+    bots: HashMap<Resource, usize>,
+    blueprint: &Blueprint,
+    memo: &mut Memo<StateKey, usize>,
+) -> usize {
+    let key = state_key(&choice, time, &resources, &bots);
+    memo.get_or_compute(key, |memo| {
+        simulate_turn(choice, time, resources, bots, blueprint, memo)
+    })
+}
+
+fn simulate_turn(
     choice: Option<(Resource, Costs)>,
     time: usize,
     mut resources: HashMap<Resource, usize>,
     mut bots: HashMap<Resource, usize>,
     blueprint: &Blueprint,
-) -> Vec<usize> {
+    memo: &mut Memo<StateKey, usize>,
+) -> usize {
     // Pay resources first...
     if let Some((_, costs)) = &choice {
         for (res, count) in &costs.0 {
@@ -175,9 +213,14 @@ fn simulate_dfs(
 
     unsafe { TURNS_SIMULATED += 1 };
 
+    // Unwind without exploring further; the caller reports the timeout
+    if aoc::cancel::is_cancelled() {
+        return 0;
+    }
+
     if time == 1 {
         unsafe { STRATEGIES_SIMULATED += 1 };
-        return vec![resources[&Resource::Geode] + bots[&Resource::Geode]];
+        return resources[&Resource::Geode] + bots[&Resource::Geode];
     }
 
     let time = time - 1;
@@ -195,29 +238,133 @@ fn simulate_dfs(
             resources[&Resource::Obsidian] + time * bots[&Resource::Obsidian] + triangular(time);
         if geode_bot_cost_in_obsidian > maximum_possible_obsidian {
             unsafe { STRATEGIES_SIMULATED += 1 };
-            return vec![resources[&Resource::Geode] + time * bots[&Resource::Geode]];
+            return resources[&Resource::Geode] + time * bots[&Resource::Geode];
         }
     }
 
-    let mut v = vec![];
+    let mut best = 0;
 
     // Simulate the option where we don't build anything, though skip it if we
     // have the choice of building any of the four bots (because then there is
     // nothing to save for)
     if opts.len() != 4 {
-        let geodes = simulate_dfs(None, time, resources.clone(), bots.clone(), blueprint);
-        v.extend(geodes);
+        let geodes = simulate_dfs(None, time, resources.clone(), bots.clone(), blueprint, memo);
+        best = best.max(geodes);
     }
 
     // Simulate all bot options
     for opt in opts {
-        let geodes = simulate_dfs(Some(opt), time, resources.clone(), bots.clone(), blueprint);
-        v.extend(geodes);
+        let geodes = simulate_dfs(
+            Some(opt),
+            time,
+            resources.clone(),
+            bots.clone(),
+            blueprint,
+            memo,
+        );
+        best = best.max(geodes);
+    }
+    best
+}
+
+/// A state of the branch and bound search: the minutes left and the resource
+/// and bot counts in the order of `RESOURCES`
+#[derive(Clone, Copy)]
+struct Factory {
+    time: usize,
+    resources: [usize; 4],
+    bots: [usize; 4],
+}
+
+impl Factory {
+    /// Returns the geodes opened by the end if no more bots are built.
+    fn geodes(&self) -> usize {
+        self.resources[Resource::Geode as usize] + self.time * self.bots[Resource::Geode as usize]
+    }
+}
+
+/// Returns the most geodes that can be opened in `time` minutes.
+fn max_geodes(blueprint: &Blueprint, time: usize) -> anyhow::Result<usize> {
+    let costs = blueprint.cost_matrix();
+    // Spending is limited to one bot a minute, so more bots of a resource
+    // than the most any bot costs of it are no use
+    let max_bots = RESOURCES.map(|res| match res {
+        Resource::Geode => usize::MAX,
+        _ => costs
+            .iter()
+            .map(|cost| cost[res as usize])
+            .max()
+            .unwrap_or(0),
+    });
+
+    // Each move is the next bot to build, after waiting until it's affordable
+    let branch = |factory: &Factory, moves: &mut Vec<Factory>| {
+        for bot in RESOURCES.into_iter().rev() {
+            let bot = bot as usize;
+            if factory.bots[bot] >= max_bots[bot] {
+                continue;
+            }
+            let wait = (0..4)
+                .map(|res| {
+                    let missing = costs[bot][res].saturating_sub(factory.resources[res]);
+                    match (missing, factory.bots[res]) {
+                        (0, _) => Some(0),
+                        (_, 0) => None,
+                        (missing, bots) => Some(missing.div_ceil(bots)),
+                    }
+                })
+                .try_fold(0, |wait, res_wait| Some(wait.max(res_wait?)));
+            // A bot built in the last minute has no time to collect anything
+            let Some(minutes) = wait.map(|wait| wait + 1).filter(|&m| m < factory.time) else {
+                continue;
+            };
+            let mut next = *factory;
+            next.time -= minutes;
+            for ((amount, bots), cost) in
+                next.resources.iter_mut().zip(factory.bots).zip(costs[bot])
+            {
+                *amount = *amount + minutes * bots - cost;
+            }
+            next.bots[bot] += 1;
+            moves.push(next);
+        }
+    };
+    // At best a geode bot is built every minute from now on
+    let bound =
+        |factory: &Factory| factory.geodes() + factory.time * factory.time.saturating_sub(1) / 2;
+    // With the same bots at the same time, having more of everything is never
+    // worse
+    let search = Search::new(branch, Factory::geodes, bound).dominance(
+        |factory| (factory.time, factory.bots),
+        |a, b| (0..4).all(|res| a.resources[res] >= b.resources[res]),
+    );
+
+    let outcome = search.run(Factory {
+        time,
+        resources: [0; 4],
+        bots: [1, 0, 0, 0],
+    });
+    aoc::phase::note(&format!("search {}", blueprint.0), outcome.stats);
+    if outcome.cancelled {
+        aoc::cancel::check(|| {
+            let (id, geodes) = (blueprint.0, outcome.value);
+            format!("blueprint {id} opens {geodes} geodes at best so far")
+        })?;
+    }
+    Ok(outcome.value)
+}
+
+/// Sums the quality levels of the blueprints, found with [`max_geodes`].
+fn quality_sum_bnb(blueprints: &[Blueprint]) -> anyhow::Result<usize> {
+    let mut sum = 0;
+    for (done, bp) in blueprints.iter().enumerate() {
+        aoc::cancel::check(|| format!("{done} of {} blueprints searched", blueprints.len()))?;
+        sum += bp.0 * max_geodes(bp, 24)?;
     }
-    v
+    Ok(sum)
 }
 
-fn simulate_all(blueprint: &Blueprint) -> Vec<usize> {
+fn simulate_all(blueprint: &Blueprint) -> usize {
     let bots: HashMap<_, _> = [
         (Resource::Ore, 1),
         (Resource::Clay, 0),
@@ -238,27 +385,97 @@ fn simulate_all(blueprint: &Blueprint) -> Vec<usize> {
     let time = 24;
 
     // Initial options available
-    let initial_opts = options(&vec![], &resources, &blueprint);
+    let initial_opts = options(&[], &resources, &blueprint);
+
+    let mut memo = Memo::with_capacity_limit(MEMO_CAPACITY);
 
     // Simulate all options
-    let mut v = vec![];
+    let mut best = 0;
     for opt in initial_opts {
-        let geodes = simulate_dfs(Some(opt), time, resources.clone(), bots.clone(), blueprint);
-        v.extend(geodes);
+        let geodes = simulate_dfs(
+            Some(opt),
+            time,
+            resources.clone(),
+            bots.clone(),
+            blueprint,
+            &mut memo,
+        );
+        best = best.max(geodes);
     }
     // Also simulate the option where we don't build anything
-    let geodes = simulate_dfs(None, time, resources.clone(), bots.clone(), blueprint);
-    v.extend(geodes);
-    v
+    let geodes = simulate_dfs(
+        None,
+        time,
+        resources.clone(),
+        bots.clone(),
+        blueprint,
+        &mut memo,
+    );
+    best = best.max(geodes);
+
+    aoc::phase::note(&format!("memo {}", blueprint.0), memo.stats());
+    best
 }
 
-static mut PERF: bool = true;
+/// Sums the quality levels of the blueprints. Saves and resumes from a
+/// checkpoint after each blueprint if the runner installed one.
+fn quality_sum(blueprints: &[Blueprint]) -> anyhow::Result<usize> {
+    let checkpoint = aoc::checkpoint::current();
+    let (mut done, mut sum) = (0, 0);
+    if let Some(record) = checkpoint
+        .as_ref()
+        .map(Checkpointer::load)
+        .transpose()?
+        .flatten()
+    {
+        if record.get::<usize>("blueprints")? != blueprints.len() {
+            anyhow::bail!("checkpoint is for a different input");
+        }
+        (done, sum) = (record.get("done")?, record.get("quality")?);
+    }
 
-fn main() -> anyhow::Result<()> {
-    let blueprints = INPUT
-        .lines()
-        .map(|line| Blueprint::parse(line).unwrap())
-        .collect_vec();
+    //.par_iter()
+    for bp in &blueprints[done..] {
+        let id = bp.0;
+        let best = simulate_all(bp);
+
+        // A cancelled simulation unwinds early, so its result is partial
+        if !aoc::cancel::is_cancelled() {
+            println!(
+                "Blueprint {} simulated: {} (quality = {})",
+                id,
+                best,
+                id * best
+            );
+            done += 1;
+            sum += id * best;
+        }
+
+        if let Some(checkpoint) = checkpoint.as_ref().filter(|c| c.due()) {
+            let record = Record::new()
+                .with("blueprints", blueprints.len())
+                .with("done", done)
+                .with("quality", sum);
+            checkpoint.save(&record)?;
+        }
+        aoc::cancel::check(|| {
+            format!(
+                "{done} of {} blueprints simulated, quality so far {sum}",
+                blueprints.len()
+            )
+        })?;
+    }
+
+    if let Some(checkpoint) = &checkpoint {
+        checkpoint.finish()?;
+    }
+    Ok(sum)
+}
+
+fn part1(blueprints: &[Blueprint]) -> anyhow::Result<usize> {
+    unsafe {
+        PERF = true;
+    }
 
     // Make a performance test thread
     let perf_thread = std::thread::spawn(|| {
@@ -284,29 +501,26 @@ fn main() -> anyhow::Result<()> {
         }
     });
 
-    let sum: usize = blueprints
-        //.par_iter()
-        .iter()
-        .map(|bp| {
-            let id = bp.0;
-            let geodes = simulate_all(bp);
-            let best = geodes.into_iter().max().unwrap();
-            println!(
-                "Blueprint {} simulated: {} (quality = {})",
-                id,
-                best,
-                id * best
-            );
-            id * best
-        })
-        .sum();
-
-    println!("Part 1: {}", sum);
-    //println!("Part 2: {}", find_zero_duplicate_window(14));
+    let sum = quality_sum(blueprints);
 
     unsafe {
         PERF = false;
     }
     perf_thread.join().unwrap();
-    Ok(())
+    sum
+}
+
+static mut PERF: bool = true;
+
+fn parse(input: &str) -> anyhow::Result<Vec<Blueprint>> {
+    Ok(parse_all(lines(Blueprint::parser()), input)?)
+}
+
+fn main() -> anyhow::Result<()> {
+    Day::new(19, INPUT, parse)
+        .part1("branch and bound", |blueprints| quality_sum_bnb(blueprints))
+        // Takes minutes even on the example, so it doesn't run with --all
+        .part1("dfs", |blueprints| part1(blueprints))
+        .on_request()
+        .main()
 }
@@ -1,111 +1,643 @@
-use core::panic;
+use anyhow::{bail, ensure, Context};
+use aoc::{
+    runner::{self, Options},
+    Day,
+};
+use itertools::Itertools;
+use std::{collections::HashSet, fmt::Write, sync::Arc};
 
 const INPUT: &str = include_str!("inputs/day2.txt");
+const RULES: &str = include_str!("inputs/day2-rps.txt");
 
-#[derive(Clone, Copy)]
-enum Choice {
-    Rock = 1,
-    Paper = 2,
-    Scissors = 3,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Outcome {
+    Loss,
+    Draw,
+    Win,
 }
 
-impl Choice {
-    fn shape_score(&self) -> u32 {
-        *self as u32
-    }
+const OUTCOMES: [Outcome; 3] = [Outcome::Loss, Outcome::Draw, Outcome::Win];
+/// The most shapes whose readings are all scored, as there are n! of them
+const MAX_DECODED_SHAPES: usize = 8;
+
+#[derive(Debug)]
+struct Shape {
+    name: String,
+    /// The letters for the shape in the opponent's column and in mine
+    theirs: char,
+    mine: char,
+    score: u32,
+}
+
+/// The rules of a game where each player shows a shape and some shapes beat
+/// others, like rock paper scissors. Shapes that don't beat each other draw.
+#[derive(Debug)]
+struct Rules {
+    shapes: Vec<Shape>,
+    /// `beats[a][b]` when shape `a` beats shape `b`
+    beats: Vec<Vec<bool>>,
+    /// The letter and score of each outcome, by `Outcome`
+    outcomes: [(char, u32); 3],
+}
+
+impl Rules {
+    /// Parses rules written one per line, ignoring blank lines and lines
+    /// starting with `#`:
+    ///
+    /// ```text
+    /// shape NAME THEIR_LETTER MY_LETTER SCORE
+    /// NAME beats NAME
+    /// loss|draw|win LETTER SCORE
+    /// ```
+    fn parse(text: &str) -> anyhow::Result<Rules> {
+        let mut shapes = Vec::<Shape>::new();
+        let mut wins = vec![];
+        let mut outcomes = [None; 3];
+        for (idx, line) in text.lines().enumerate() {
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            let letter = |word: &str| {
+                let mut chars = word.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(c),
+                    _ => bail!("expected a letter, found {word:?}"),
+                }
+            };
+            let rule = match words[..] {
+                [] => continue,
+                [first, ..] if first.starts_with('#') => continue,
+                ["shape", name, theirs, mine, score] => (|| {
+                    shapes.push(Shape {
+                        name: name.to_string(),
+                        theirs: letter(theirs)?,
+                        mine: letter(mine)?,
+                        score: score.parse()?,
+                    });
+                    Ok(())
+                })(),
+                [winner, "beats", loser] => {
+                    wins.push((winner, loser));
+                    Ok(())
+                }
+                [outcome @ ("loss" | "draw" | "win"), c, score] => (|| {
+                    let idx = ["loss", "draw", "win"].iter().position(|&o| o == outcome);
+                    outcomes[idx.unwrap()] = Some((letter(c)?, score.parse()?));
+                    Ok(())
+                })(),
+                _ => Err(anyhow::anyhow!("unknown rule")),
+            };
+            rule.with_context(|| format!("rule {}: {line:?}", idx + 1))?;
+        }
 
-    fn from_letter(c: char) -> Self {
-        match c {
-            'A' | 'X' => Choice::Rock,
-            'B' | 'Y' => Choice::Paper,
-            'C' | 'Z' => Choice::Scissors,
-            _ => panic!("invalid letter"),
+        let shape = |name| {
+            let idx = shapes.iter().position(|shape| shape.name == name);
+            idx.with_context(|| format!("unknown shape {name:?}"))
+        };
+        let mut beats = vec![vec![false; shapes.len()]; shapes.len()];
+        for (winner, loser) in wins {
+            let (winner, loser) = (shape(winner)?, shape(loser)?);
+            ensure!(
+                winner != loser && !beats[loser][winner],
+                "{} can't beat {}",
+                shapes[winner].name,
+                shapes[loser].name
+            );
+            beats[winner][loser] = true;
         }
+        let Some(outcomes) = outcomes.iter().copied().collect::<Option<Vec<_>>>() else {
+            bail!("the rules need the letters and scores of loss, draw and win");
+        };
+
+        for letters in [
+            shapes.iter().map(|shape| shape.name.clone()).collect(),
+            shapes
+                .iter()
+                .map(|shape| shape.theirs.to_string())
+                .collect(),
+            shapes.iter().map(|shape| shape.mine.to_string()).collect(),
+            outcomes
+                .iter()
+                .map(|(c, _)| c.to_string())
+                .collect::<Vec<_>>(),
+        ] {
+            let unique = letters.iter().collect::<HashSet<_>>();
+            ensure!(unique.len() == letters.len(), "one of {letters:?} repeats");
+        }
+
+        Ok(Rules {
+            shapes,
+            beats,
+            outcomes: [outcomes[0], outcomes[1], outcomes[2]],
+        })
+    }
+
+    fn theirs(&self, c: char) -> anyhow::Result<usize> {
+        let idx = self.shapes.iter().position(|shape| shape.theirs == c);
+        idx.with_context(|| format!("no shape for the opponent's letter {c:?}"))
+    }
+
+    /// Returns the index of the shape for my letter `c`
+    fn mine(&self, c: char) -> anyhow::Result<usize> {
+        let idx = self.shapes.iter().position(|shape| shape.mine == c);
+        idx.with_context(|| format!("no shape for my letter {c:?}"))
+    }
+
+    /// Returns the index of the outcome for the letter `c`
+    fn outcome_of(&self, c: char) -> anyhow::Result<usize> {
+        let idx = self.outcomes.iter().position(|&(o, _)| o == c);
+        idx.with_context(|| format!("no outcome for the letter {c:?}"))
     }
 
-    fn beats(&self, other: Choice) -> bool {
-        match (self, other) {
-            (Choice::Rock, Choice::Scissors) => true,
-            (Choice::Paper, Choice::Rock) => true,
-            (Choice::Scissors, Choice::Paper) => true,
-            _ => false,
+    fn outcome(&self, me: usize, them: usize) -> Outcome {
+        if self.beats[me][them] {
+            Outcome::Win
+        } else if self.beats[them][me] {
+            Outcome::Loss
+        } else {
+            Outcome::Draw
         }
     }
 
-    fn pick_lose(&self) -> Self {
+    fn score(&self, me: usize, them: usize) -> u32 {
+        self.shapes[me].score + self.outcomes[self.outcome(me, them) as usize].1
+    }
+
+    /// Returns the shape that gets `outcome` against `them`, the highest
+    /// scoring one if several do.
+    fn pick(&self, them: usize, outcome: Outcome) -> anyhow::Result<usize> {
+        let picks = (0..self.shapes.len()).filter(|&me| self.outcome(me, them) == outcome);
+        picks
+            .max_by_key(|&me| self.shapes[me].score)
+            .with_context(|| {
+                let name = &self.shapes[them].name;
+                format!("no shape gets a {outcome:?} against {name}")
+            })
+    }
+}
+
+/// A way to read the second column of the guide
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Reading {
+    /// The shape to show for each of my letters, in the order of the shapes
+    Shapes(Vec<usize>),
+    /// The outcome to get for each outcome letter, in the order of `Outcome`
+    Outcomes([Outcome; 3]),
+}
+
+impl Reading {
+    /// Every reading: each way to assign my letters to the shapes, and each
+    /// way to assign the outcome letters to the outcomes
+    fn all(rules: &Rules) -> anyhow::Result<Vec<Reading>> {
+        let n = rules.shapes.len();
+        ensure!(
+            n <= MAX_DECODED_SHAPES,
+            "{n} shapes have too many readings to score, at most {MAX_DECODED_SHAPES} do"
+        );
+        let shapes = (0..n).permutations(n).map(Reading::Shapes);
+        let outcomes = OUTCOMES
+            .into_iter()
+            .permutations(3)
+            .map(|o| Reading::Outcomes([o[0], o[1], o[2]]));
+        Ok(shapes.chain(outcomes).collect())
+    }
+
+    fn score(&self, rules: &Rules, guide: &[(char, char)]) -> anyhow::Result<u32> {
+        guide.iter().try_fold(0, |total, &(them, c)| {
+            let them = rules.theirs(them)?;
+            let me = match self {
+                Reading::Shapes(shapes) => shapes[rules.mine(c)?],
+                Reading::Outcomes(outcomes) => rules.pick(them, outcomes[rules.outcome_of(c)?])?,
+            };
+            Ok(total + rules.score(me, them))
+        })
+    }
+
+    fn describe(&self, rules: &Rules) -> String {
         match self {
-            Choice::Rock => Choice::Scissors,
-            Choice::Paper => Choice::Rock,
-            Choice::Scissors => Choice::Paper,
+            Reading::Shapes(shapes) => rules
+                .shapes
+                .iter()
+                .zip(shapes)
+                .map(|(shape, &me)| format!("{}={}", shape.mine, rules.shapes[me].name))
+                .join(" "),
+            Reading::Outcomes(outcomes) => rules
+                .outcomes
+                .iter()
+                .zip(outcomes)
+                .map(|((c, _), outcome)| format!("{c}={outcome:?}"))
+                .join(" "),
         }
     }
-    fn pick_win(&self) -> Self {
-        match self {
-            Choice::Rock => Choice::Paper,
-            Choice::Paper => Choice::Scissors,
-            Choice::Scissors => Choice::Rock,
+}
+
+/// Scores the guide read as the shapes to show
+fn part1(rules: &Rules, guide: &[(char, char)]) -> anyhow::Result<u32> {
+    let reading = Reading::Shapes((0..rules.shapes.len()).collect());
+    reading.score(rules, guide)
+}
+
+/// Scores the guide read as the outcomes to get
+fn part2(rules: &Rules, guide: &[(char, char)]) -> anyhow::Result<u32> {
+    Reading::Outcomes(OUTCOMES).score(rules, guide)
+}
+
+/// Scores the guide under every reading, or `None` where it has letters the
+/// reading doesn't know
+fn decode(rules: &Rules, guide: &[(char, char)]) -> anyhow::Result<Vec<(Reading, Option<u32>)>> {
+    let readings = Reading::all(rules)?
+        .into_iter()
+        .map(|reading| {
+            let score = reading.score(rules, guide).ok();
+            (reading, score)
+        })
+        .collect();
+    Ok(readings)
+}
+
+/// Returns the indices of the readings whose scores are closest to the
+/// claimed total, all of them if several are as close.
+fn most_plausible(decoded: &[(Reading, Option<u32>)], claim: u32) -> Vec<usize> {
+    let distance = |score: &Option<u32>| score.map(|score| score.abs_diff(claim));
+    let Some(best) = decoded
+        .iter()
+        .filter_map(|(_, score)| distance(score))
+        .min()
+    else {
+        return vec![];
+    };
+    (0..decoded.len())
+        .filter(|&idx| distance(&decoded[idx].1) == Some(best))
+        .collect()
+}
+
+/// My score for each of my shapes against each of the opponent's
+fn payoff(rules: &Rules) -> Vec<Vec<f64>> {
+    let n = rules.shapes.len();
+    (0..n)
+        .map(|me| (0..n).map(|them| rules.score(me, them) as f64).collect())
+        .collect()
+}
+
+/// How often the opponent shows each shape in the guide
+fn empirical(rules: &Rules, guide: &[(char, char)]) -> anyhow::Result<Vec<f64>> {
+    let mut counts = vec![0.0; rules.shapes.len()];
+    for &(them, _) in guide {
+        counts[rules.theirs(them)?] += 1.0;
+    }
+    ensure!(!guide.is_empty(), "the guide has no rounds");
+    Ok(counts
+        .iter()
+        .map(|count| count / guide.len() as f64)
+        .collect())
+}
+
+/// My expected score per round when I play the mixed strategy `mine` and the
+/// opponent plays `theirs`
+fn expected(payoff: &[Vec<f64>], mine: &[f64], theirs: &[f64]) -> f64 {
+    let row = |(scores, p): (&Vec<f64>, &f64)| {
+        p * scores.iter().zip(theirs).map(|(s, q)| s * q).sum::<f64>()
+    };
+    payoff.iter().zip(mine).map(row).sum()
+}
+
+/// Returns the shape that scores the most against `theirs`, and its
+/// expected score per round.
+fn best_response(payoff: &[Vec<f64>], theirs: &[f64]) -> (usize, f64) {
+    let pure = |me| {
+        let mut mine = vec![0.0; payoff.len()];
+        mine[me] = 1.0;
+        expected(payoff, &mine, theirs)
+    };
+    (0..payoff.len())
+        .map(|me| (me, pure(me)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("a game has shapes")
+}
+
+/// Strategies that neither player gains from leaving alone, when I try to
+/// score the most and the opponent tries to let me score the least.
+#[derive(Debug)]
+struct Equilibrium {
+    mine: Vec<f64>,
+    theirs: Vec<f64>,
+    /// My expected score per round
+    value: f64,
+}
+
+const EPSILON: f64 = 1e-9;
+
+/// Solves the zero sum game with the payoff matrix by the simplex method.
+///
+/// With the payoffs shifted to be at least 1, the opponent's strategy is
+/// `w / sum(w)` for the `w >= 0` with the largest sum such that `payoff * w
+/// <= 1`, the value of the game is `1 / sum(w)`, and my strategy comes from
+/// the dual of that program.
+fn equilibrium(payoff: &[Vec<f64>]) -> Equilibrium {
+    let (m, n) = (payoff.len(), payoff[0].len());
+    let shift = payoff.iter().flatten().copied().fold(f64::MAX, f64::min) - 1.0;
+
+    // A row per shape of mine and the objective row, over the columns w, the
+    // slacks and the right hand side
+    let mut table = vec![vec![0.0; n + m + 1]; m + 1];
+    for (i, row) in table.iter_mut().take(m).enumerate() {
+        for (j, cell) in row.iter_mut().take(n).enumerate() {
+            *cell = payoff[i][j] - shift;
         }
+        row[n + i] = 1.0;
+        row[n + m] = 1.0;
+    }
+    table[m][..n].fill(-1.0);
+    let mut basis = (n..n + m).collect::<Vec<_>>();
+
+    // Bland's rule: the first improving column and the leaving variable with
+    // the lowest index, which can't cycle
+    while let Some(col) = (0..n + m).find(|&col| table[m][col] < -EPSILON) {
+        let row = (0..m)
+            .filter(|&row| table[row][col] > EPSILON)
+            .min_by(|&a, &b| {
+                let ratio = |row: usize| table[row][n + m] / table[row][col];
+                ratio(a).total_cmp(&ratio(b)).then(basis[a].cmp(&basis[b]))
+            })
+            .expect("the program is bounded");
+        let pivot = table[row][col];
+        table[row].iter_mut().for_each(|cell| *cell /= pivot);
+        let pivot_row = table[row].clone();
+        for (other, cells) in table.iter_mut().enumerate() {
+            let factor = cells[col];
+            if other != row && factor != 0.0 {
+                for (cell, p) in cells.iter_mut().zip(&pivot_row) {
+                    *cell -= factor * p;
+                }
+            }
+        }
+        basis[row] = col;
+    }
+
+    let sum = table[m][n + m];
+    let mut theirs = vec![0.0; n];
+    for (row, &var) in basis.iter().enumerate() {
+        if var < n {
+            theirs[var] = table[row][n + m] / sum;
+        }
+    }
+    let mine = (0..m).map(|i| table[m][n + i] / sum).collect();
+    Equilibrium {
+        mine,
+        theirs,
+        value: 1.0 / sum + shift,
     }
 }
 
-// Part 1
-fn part1(toks: &[(char, char)]) -> u32 {
-    toks.iter()
-        .map(|(opp, me)| {
-            let opp = Choice::from_letter(*opp);
-            let me = Choice::from_letter(*me);
+/// The strategy guide and the rules to read it by
+struct Game {
+    rules: Arc<Rules>,
+    guide: Vec<(char, char)>,
+}
 
-            let round_score = me.shape_score()
-                + if me.beats(opp) {
-                    6
-                } else if opp.beats(me) {
-                    0
-                } else {
-                    3
-                };
-            round_score
+fn parse(input: &str) -> anyhow::Result<Vec<(char, char)>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            let mut chars = line.chars();
+            match (chars.next(), chars.next(), chars.next(), chars.next()) {
+                (Some(them), Some(' '), Some(me), None) => Ok((them, me)),
+                _ => bail!("line {}: expected two letters, found {line:?}", idx + 1),
+            }
         })
-        .sum::<u32>()
-}
-
-fn part2(toks: &[(char, char)]) -> u32 {
-    toks.iter()
-        .map(|(opp, me)| {
-            let opp = Choice::from_letter(*opp);
-            let me = match me {
-                'X' => opp.pick_lose(),
-                'Y' => opp,
-                'Z' => opp.pick_win(),
-                _ => panic!(),
-            };
+        .collect()
+}
+
+#[test]
+fn scores_any_rules() {
+    let example = parse("A Y\nB X\nC Z").unwrap();
+    let rps = Rules::parse(RULES).unwrap();
+    assert_eq!(part1(&rps, &example).unwrap(), 15);
+    assert_eq!(part2(&rps, &example).unwrap(), 12);
 
-            let round_score = me.shape_score()
-                + if me.beats(opp) {
-                    6
-                } else if opp.beats(me) {
-                    0
-                } else {
-                    3
-                };
-            round_score
+    // Spock (Z) vaporizes rock, scissors (C) decapitate lizard (Y) and
+    // paper (B) disproves Spock
+    let rpsls = Rules::parse(include_str!("inputs/day2-rpsls.txt")).unwrap();
+    let guide = parse("A Z\nC Y\nB Z").unwrap();
+    assert_eq!(part1(&rpsls, &guide).unwrap(), 11 + 4 + 5);
+    // Both scissors and lizard beat paper, and lizard scores more
+    assert_eq!(part2(&rpsls, &guide).unwrap(), 11 + 6 + 10);
+    assert!(part1(&rps, &parse("E V").unwrap()).is_err());
+
+    // The puzzle's two readings are among those of the example. Only the
+    // outcome reading explains 12, but 15 fits most of them.
+    let decoded = decode(&rps, &example).unwrap();
+    assert_eq!(decoded.len(), 6 + 6);
+    assert!(decoded.contains(&(Reading::Shapes(vec![0, 1, 2]), Some(15))));
+    let plausible = most_plausible(&decoded, 12);
+    assert_eq!(plausible.len(), 1);
+    assert_eq!(
+        decoded[plausible[0]],
+        (Reading::Outcomes(OUTCOMES), Some(12))
+    );
+    assert_eq!(most_plausible(&decoded, 15).len(), 8);
+    let closest = &decoded[most_plausible(&decoded, 100)[0]].0;
+    assert_eq!(closest.describe(&rps), "X=scissors Y=paper Z=rock");
+
+    assert!(Rules::parse("shape rock A X 1\nrock beats rock").is_err());
+    assert!(Rules::parse("shape rock A X 1\nloss X 0\ndraw Y 3").is_err());
+    assert!(Rules::parse("shape rock A X 1\nshape paper A Y 2").is_err());
+    assert!(Rules::parse("rock covers paper").is_err());
+
+    // Nine shapes would have 9! readings
+    let mut many = (0..9)
+        .map(|i| {
+            let (theirs, mine) = ((b'A' + i) as char, (b'R' + i) as char);
+            format!("shape s{i} {theirs} {mine} {i}\n")
         })
-        .sum::<u32>()
+        .collect::<String>();
+    many.push_str("loss R 0\ndraw S 3\nwin T 6\n");
+    let many = Rules::parse(&many).unwrap();
+    assert!(decode(&many, &example).is_err());
+}
+
+/// Reports the score of the guide under each reading of its second column,
+/// flagging the readings closest to the claimed total if there is one.
+fn readings(game: &Game, claim: Option<u32>) -> anyhow::Result<String> {
+    let decoded = decode(&game.rules, &game.guide)?;
+    let plausible = claim.map_or(vec![], |claim| most_plausible(&decoded, claim));
+    let mut report = String::new();
+    for (idx, (reading, score)) in decoded.iter().enumerate() {
+        let score = score.map_or("-".to_string(), |score| score.to_string());
+        let flag = if plausible.contains(&idx) {
+            "  <- most plausible"
+        } else {
+            ""
+        };
+        let reading = reading.describe(&game.rules);
+        let _ = writeln!(report, "{reading:<40} {score:>8}{flag}");
+    }
+    if plausible.len() > 1 {
+        let _ = writeln!(
+            report,
+            "{} readings fit the claim equally well",
+            plausible.len()
+        );
+    }
+    Ok(report)
+}
+
+/// Reports the opponent's moves in the guide, my best answer to them, the
+/// equilibrium of the game, and the expected score of `mix` if given.
+fn strategy(game: &Game, mix: Option<&[f64]>) -> anyhow::Result<String> {
+    let Game { rules, guide } = game;
+    let payoff = payoff(rules);
+    let names = rules
+        .shapes
+        .iter()
+        .map(|shape| &shape.name)
+        .collect::<Vec<_>>();
+    let show = |strategy: &[f64]| {
+        names
+            .iter()
+            .zip(strategy)
+            .map(|(name, p)| format!("{name} {p:.3}"))
+            .join(", ")
+    };
+
+    let mut report = String::new();
+    let theirs = empirical(rules, guide)?;
+    let _ = writeln!(report, "The opponent plays {}", show(&theirs));
+    let (best, score) = best_response(&payoff, &theirs);
+    let _ = writeln!(
+        report,
+        "Best response: {} for {score:.3} a round",
+        names[best]
+    );
+    let eq = equilibrium(&payoff);
+    let _ = writeln!(
+        report,
+        "Equilibrium: {} for {:.3} a round",
+        show(&eq.mine),
+        eq.value
+    );
+    let _ = writeln!(report, "  against {}", show(&eq.theirs));
+    let against = expected(&payoff, &eq.mine, &theirs);
+    let _ = writeln!(
+        report,
+        "  which scores {against:.3} a round against this opponent"
+    );
+    if let Some(mix) = mix {
+        let probability = |p: &f64| (-1e-6..=1.0 + 1e-6).contains(p);
+        ensure!(
+            mix.len() == names.len()
+                && mix.iter().all(probability)
+                && (mix.iter().sum::<f64>() - 1.0).abs() < 1e-6,
+            "the mix needs a probability from 0 to 1 for each of {} shapes, summing to 1",
+            names.len()
+        );
+        let score = expected(&payoff, mix, &theirs);
+        let _ = writeln!(report, "Mix {} scores {score:.3} a round", show(mix));
+    }
+    Ok(report)
 }
 
+#[test]
+fn finds_equilibria() {
+    let rps = Rules::parse(RULES).unwrap();
+    let rpsls = Rules::parse(include_str!("inputs/day2-rpsls.txt")).unwrap();
+    for rules in [&rps, &rpsls] {
+        let payoff = payoff(rules);
+        let eq = equilibrium(&payoff);
+        for strategy in [&eq.mine, &eq.theirs] {
+            assert!((strategy.iter().sum::<f64>() - 1.0).abs() < 1e-6);
+            assert!(strategy.iter().all(|&p| p >= 0.0));
+        }
+        // No pure strategy does better against the other player's strategy
+        for shape in 0..payoff.len() {
+            let mut pure = vec![0.0; payoff.len()];
+            pure[shape] = 1.0;
+            assert!(expected(&payoff, &pure, &eq.theirs) <= eq.value + 1e-6);
+            assert!(expected(&payoff, &eq.mine, &pure) >= eq.value - 1e-6);
+        }
+        assert!((expected(&payoff, &eq.mine, &eq.theirs) - eq.value).abs() < 1e-6);
+    }
+
+    // In rock paper scissors I mix evenly, and the opponent shies from paper,
+    // which gives me the most points for beating it
+    let eq = equilibrium(&payoff(&rps));
+    let close = |a: &[f64], b: &[f64]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-6);
+    assert!(close(&eq.mine, &[1.0 / 3.0; 3]));
+    assert!(close(&eq.theirs, &[4.0 / 9.0, 1.0 / 9.0, 4.0 / 9.0]));
+    assert!((eq.value - 5.0).abs() < 1e-6);
+
+    // An opponent who mostly shows rock is best met with paper
+    let guide = parse("A X\nA X\nB X").unwrap();
+    let theirs = empirical(&rps, &guide).unwrap();
+    assert_eq!(
+        best_response(&payoff(&rps), &theirs),
+        (1, 8.0 * 2.0 / 3.0 + 5.0 / 3.0)
+    );
+
+    let game = Game {
+        rules: Arc::new(rps),
+        guide,
+    };
+    let report = strategy(&game, Some(&[0.0, 1.0, 0.0])).unwrap();
+    assert!(report.ends_with("Mix rock 0.000, paper 1.000, scissors 0.000 scores 7.000 a round\n"));
+    assert!(strategy(&game, Some(&[0.5, 0.5])).is_err());
+    assert!(strategy(&game, Some(&[1.5, -0.5, 0.0])).is_err());
+}
+
+/// Takes the runner's options, and `--rules PATH` to play by the rules in
+/// PATH instead of rock paper scissors.
 fn main() -> anyhow::Result<()> {
-    let toks: Vec<(char, char)> = INPUT
-        .lines()
-        .map(|line| {
-            let mut toks = line.split_ascii_whitespace();
-            let opp = toks.nth(0).unwrap().chars().nth(0).unwrap();
-            let me = toks.nth(0).unwrap().chars().nth(0).unwrap();
-            (opp, me)
-        })
-        .collect();
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let rules = match runner::take_value(&mut args, "--rules")? {
+        Some(path) => fs_err::read_to_string(path)?,
+        None => RULES.to_string(),
+    };
+    let rules = Arc::new(Rules::parse(&rules).context("invalid rules")?);
 
-    let answer = part1(&toks);
-    println!("{answer}");
-    let answer = part2(&toks);
-    println!("{answer}");
-    Ok(())
+    let day = Day::new(2, INPUT, move |input| {
+        Ok(Game {
+            rules: rules.clone(),
+            guide: parse(input)?,
+        })
+    })
+    .part1("rules", |game| part1(&game.rules, &game.guide))
+    .part2("rules", |game| part2(&game.rules, &game.guide))
+    .mode(
+        "decode",
+        None,
+        "score every reading of the guide",
+        |game, _| readings(game, None),
+    )
+    .mode(
+        "claim",
+        Some("TOTAL"),
+        "score every reading of the guide and flag those that best explain a claimed total",
+        |game, claim| {
+            let claim = claim.unwrap_or_default();
+            let claim = claim
+                .parse()
+                .with_context(|| format!("invalid claimed total: {claim}"))?;
+            readings(game, Some(claim))
+        },
+    )
+    .mode(
+        "strategy",
+        None,
+        "analyse the game against the opponent of the guide",
+        |game, _| strategy(game, None),
+    )
+    .mode(
+        "mix",
+        Some("P,P,..."),
+        "analyse the game and score a mixed strategy with the given probability for each shape",
+        |game, mix| {
+            let mix = mix.unwrap_or_default();
+            let mix = mix
+                .split(',')
+                .map(str::parse)
+                .collect::<Result<Vec<f64>, _>>()
+                .with_context(|| format!("invalid mix: {mix}"))?;
+            strategy(game, Some(&mix))
+        },
+    );
+    runner::run(&day, &Options::parse_for(&day, args)?)
 }
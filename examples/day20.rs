@@ -1,22 +1,26 @@
+use aoc::{
+    num::{self, Int, Overflow},
+    Day,
+};
 use itertools::Itertools;
 
 const INPUT: &str = include_str!("inputs/day20.txt");
 
-fn new_pos(old_pos: usize, value: isize, len: usize) -> usize {
-    let len = len - 1;
-    let new_pos = old_pos as isize + value;
-    (((new_pos % len as isize) + len as isize) % len as isize) as usize
+fn new_pos(old_pos: usize, value: Int, len: usize) -> Result<usize, Overflow> {
+    let len = len as Int - 1;
+    let new_pos = num::add(old_pos as Int, value)?;
+    Ok(num::rem(new_pos, len)? as usize)
 }
 
-fn collect(positions: &[usize], file: &[isize]) -> Vec<isize> {
+fn collect(positions: &[usize], file: &[Int]) -> Vec<Int> {
     positions.iter().map(|pos| file[*pos]).collect_vec()
 }
 
-fn mix(positions: &mut Vec<usize>, file: &[isize]) {
+fn mix(positions: &mut Vec<usize>, file: &[Int]) -> Result<(), Overflow> {
     for pos_idx in 0..file.len() {
         let curpos = positions.iter().position(|p| *p == pos_idx).unwrap();
         let value = file[pos_idx];
-        let npos = new_pos(curpos, value, file.len());
+        let npos = new_pos(curpos, value, file.len())?;
 
         // Remove at the location where the idx is currently
         let item = positions.remove(curpos);
@@ -24,32 +28,36 @@ fn mix(positions: &mut Vec<usize>, file: &[isize]) {
         // Insert the item at new position
         positions.insert(npos, item);
     }
+    Ok(())
 }
 
-fn mix_and_collect(file: &[isize]) -> Vec<isize> {
+fn mix_and_collect(file: &[Int]) -> Result<Vec<Int>, Overflow> {
     let mut positions = (0..file.len()).collect_vec();
-    mix(&mut positions, &file);
-    collect(&positions, &file)
+    mix(&mut positions, &file)?;
+    Ok(collect(&positions, &file))
 }
 
-fn part1(file: &[isize]) -> isize {
-    let mixed = mix_and_collect(&file);
+fn part1(file: &[Int]) -> Result<Int, Overflow> {
+    let mixed = mix_and_collect(&file)?;
 
     let pos_of_zero = mixed.iter().position(|v| *v == 0).unwrap();
 
     let n1 = mixed[(pos_of_zero + 1000) % mixed.len()];
     let n2 = mixed[(pos_of_zero + 2000) % mixed.len()];
     let n3 = mixed[(pos_of_zero + 3000) % mixed.len()];
-    n1 + n2 + n3
+    num::add(num::add(n1, n2)?, n3)
 }
 
-fn part2(file: &[isize]) -> isize {
+fn part2(file: &[Int]) -> Result<Int, Overflow> {
     let key = 811589153;
-    let file = file.iter().map(|x| *x * key).collect_vec();
+    let file = file
+        .iter()
+        .map(|x| num::mul(*x, key))
+        .collect::<Result<Vec<_>, _>>()?;
 
     let mut positions = (0..file.len()).collect_vec();
     for _ in 0..10 {
-        mix(&mut positions, &file);
+        mix(&mut positions, &file)?;
     }
     let mixed = collect(&positions, &file);
 
@@ -58,17 +66,19 @@ fn part2(file: &[isize]) -> isize {
     let n1 = mixed[(pos_of_zero + 1000) % mixed.len()];
     let n2 = mixed[(pos_of_zero + 2000) % mixed.len()];
     let n3 = mixed[(pos_of_zero + 3000) % mixed.len()];
-    n1 + n2 + n3
+    num::add(num::add(n1, n2)?, n3)
 }
 
-fn main() -> anyhow::Result<()> {
-    let file = INPUT
+fn parse(input: &str) -> anyhow::Result<Vec<Int>> {
+    Ok(input
         .lines()
-        .map(|line| line.parse::<isize>().unwrap())
-        .collect_vec();
-
-    println!("Part 2: {}", part1(&file));
-    println!("Part 2: {}", part2(&file));
+        .map(|line| line.parse::<Int>())
+        .collect::<Result<_, _>>()?)
+}
 
-    Ok(())
+fn main() -> anyhow::Result<()> {
+    Day::new(20, INPUT, parse)
+        .part1("mix", |file| part1(file))
+        .part2("mix", |file| part2(file))
+        .main()
 }
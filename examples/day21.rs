@@ -1,63 +1,93 @@
+use aoc::{
+    memo::Memo,
+    num::{self, Int, Op},
+    parse::{key_value, lines, parse_all, tag, take_while1, uint, word, Parser},
+    Day,
+};
 use std::collections::HashMap;
 
-#[derive(Clone)]
-enum Op {
-    Add,
-    Sub,
-    Mul,
-    Div,
-}
-
 #[derive(Clone)]
 enum Job {
-    Num(usize),
+    Num(Int),
     Op(Op, [String; 2]),
 }
 
 const INPUT: &str = include_str!("inputs/day21.txt");
 
-fn resolve(start: &str, monkeys: &HashMap<String, Job>) -> usize {
-    match &monkeys[start] {
-        Job::Num(num) => *num,
+fn resolve(
+    start: &str,
+    monkeys: &HashMap<String, Job>,
+    memo: &mut Memo<String, Int>,
+) -> anyhow::Result<Int> {
+    Ok(resolve_tracked(start, monkeys, memo)?.0)
+}
+
+/// Returns the number yelled by `start` and whether it depends on "humn".
+/// Only the values that do not depend on "humn" are cached, so the cache stays
+/// valid when part 2 changes the human's number.
+fn resolve_tracked(
+    start: &str,
+    monkeys: &HashMap<String, Job>,
+    memo: &mut Memo<String, Int>,
+) -> anyhow::Result<(Int, bool)> {
+    if let Some(num) = memo.get(start) {
+        return Ok((num, false));
+    }
+
+    let (num, depends_on_humn) = match &monkeys[start] {
+        Job::Num(num) => (*num, start == "humn"),
         Job::Op(op, params) => {
-            let (left, right) = (&params[0], &params[1]);
-            let (left, right) = (resolve(left, monkeys), resolve(right, monkeys));
-            match op {
-                Op::Add => left + right,
-                Op::Sub => left - right,
-                Op::Mul => left * right,
-                Op::Div => left / right,
-            }
+            let (left, left_humn) = resolve_tracked(&params[0], monkeys, memo)?;
+            let (right, right_humn) = resolve_tracked(&params[1], monkeys, memo)?;
+            (op.apply(left, right)?, left_humn || right_humn)
         }
+    };
+
+    if !depends_on_humn {
+        memo.insert(start.to_string(), num);
     }
+    Ok((num, depends_on_humn))
 }
 
-fn part1(monkeys: &HashMap<String, Job>) -> usize {
-    resolve("root", monkeys)
+fn part1(monkeys: &HashMap<String, Job>) -> anyhow::Result<Int> {
+    let mut memo = Memo::new();
+    let num = resolve("root", monkeys, &mut memo)?;
+    aoc::phase::note("memo", memo.stats());
+    Ok(num)
 }
 
-fn err(x: usize, left: &str, right: &str, monkeys: &mut HashMap<String, Job>) -> isize {
+fn err(
+    x: Int,
+    left: &str,
+    right: &str,
+    monkeys: &mut HashMap<String, Job>,
+    memo: &mut Memo<String, Int>,
+) -> anyhow::Result<Int> {
     *monkeys.get_mut("humn").unwrap() = Job::Num(x);
 
-    let (left, right) = (resolve(left, &monkeys), resolve(right, &monkeys));
-    left as isize - right as isize
+    let (left, right) = (
+        resolve(left, monkeys, memo)?,
+        resolve(right, monkeys, memo)?,
+    );
+    Ok(num::sub(left, right)?)
 }
 
 // N.b. somehow this converges on a number that's one too big ':D
-fn part2(mut monkeys: HashMap<String, Job>) -> usize {
+fn part2(mut monkeys: HashMap<String, Job>) -> anyhow::Result<Int> {
     let root_job = monkeys.remove("root").unwrap();
 
     if let Job::Op(_, sources) = root_job {
         let (left, right) = (&sources[0], &sources[1]);
 
         let mut x = 1_000_000_000_000.;
+        let mut memo = Memo::new();
 
         for _ in 0..10 {
             let x1 = x + (x / 1_000_000_000.);
 
-            let y = err(x as usize, left, right, &mut monkeys) as f64;
+            let y = err(x as Int, left, right, &mut monkeys, &mut memo)? as f64;
 
-            let y1 = err(x1 as usize, left, right, &mut monkeys) as f64;
+            let y1 = err(x1 as Int, left, right, &mut monkeys, &mut memo)? as f64;
 
             let diff_y = y1 - y;
             let diff_x = x1 - x;
@@ -69,60 +99,41 @@ fn part2(mut monkeys: HashMap<String, Job>) -> usize {
             x -= y / diff;
         }
 
-        return x as usize;
+        aoc::phase::note("memo", memo.stats());
+        return Ok(x as Int);
     }
 
-    0
+    Ok(0)
 }
 
-fn main() -> anyhow::Result<()> {
-    let mut jobs = HashMap::new();
-
-    for line in INPUT.lines() {
-        let (name_str, job_str) = line.split_once(':').unwrap();
-        let job_str = job_str.trim();
-
-        let job = match job_str.parse::<usize>() {
-            Ok(num) => Job::Num(num),
-            // If it's not a number, it's an op
-            Err(_) => {
-                let mut job_toks = job_str.split_whitespace();
-
-                let left_str = job_toks.next().unwrap().trim().to_string();
-                let op_str = job_toks.next().unwrap();
-                let right_str = job_toks.next().unwrap().to_string();
-
-                debug_assert!(left_str.len() == 4);
-                debug_assert!(op_str.len() == 1);
-                debug_assert!(right_str.len() == 4);
-
-                let op = match op_str.chars().nth(0).unwrap() {
-                    '+' => Op::Add,
-                    '-' => Op::Sub,
-                    '*' => Op::Mul,
-                    '/' => Op::Div,
-                    c => panic!("unknown op: '{}' from \"{}\"", c, op_str),
-                };
-                Job::Op(op, [left_str, right_str])
-            }
-        };
-        jobs.insert(name_str.to_string(), job);
-    }
-    println!("Part 1: {}", part1(&jobs));
-    println!("Part 2: {}", part2(jobs.clone()));
-
-    let job = jobs.remove("root").unwrap();
-    if let Job::Op(_, sources) = job {
-        // I figured this out with the part2 Newton's iteration above
-        *jobs.get_mut("humn").unwrap() = Job::Num(3093175982595);
-        /* 3093175982596 */
-        let (left, right) = (&sources[0], &sources[1]);
-        println!("left: {}, right: {}", left, right);
-        let left = resolve(left, &jobs);
-        let right = resolve(right, &jobs);
-        println!("left: {}, right: {}", left, right);
-        println!("diff: {}", left - right);
-    }
+/// Parses a job like `5` or `pppw + sjmn`.
+fn job<'a>() -> impl Parser<'a, Job> {
+    let op = take_while1("an operator", |c| "+-*/".contains(c)).map(|op| match op {
+        "+" => Op::Add,
+        "-" => Op::Sub,
+        "*" => Op::Mul,
+        _ => Op::Div,
+    });
+    let op_job = word()
+        .skip(tag(" "))
+        .then(op)
+        .skip(tag(" "))
+        .then(word())
+        .map(|((left, op), right)| Job::Op(op, [left.to_string(), right.to_string()]));
+    uint().map(Job::Num).or(op_job)
+}
+
+fn parse(input: &str) -> anyhow::Result<HashMap<String, Job>> {
+    let jobs = parse_all(lines(key_value(word(), ": ", job())), input)?;
+    Ok(jobs
+        .into_iter()
+        .map(|(name, job)| (name.to_string(), job))
+        .collect())
+}
 
-    Ok(())
+fn main() -> anyhow::Result<()> {
+    Day::new(21, INPUT, parse)
+        .part1("resolve", |jobs| part1(jobs))
+        .part2("newton", |jobs| part2(jobs.clone()))
+        .main()
 }
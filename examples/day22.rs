@@ -1,6 +1,9 @@
 use core::panic;
 
-use itertools::Itertools;
+use aoc::{
+    parse::{grid, parse_all, tag, uint, Parser},
+    Day,
+};
 
 const INPUT: &str = include_str!("inputs/day22.txt");
 
@@ -11,13 +14,13 @@ enum Tile {
     Wall,
 }
 
-impl From<char> for Tile {
-    fn from(c: char) -> Self {
+impl Tile {
+    fn from_char(c: char) -> Option<Tile> {
         match c {
-            ' ' => Tile::Off,
-            '.' => Tile::Empty,
-            '#' => Tile::Wall,
-            _ => panic!("Invalid char for tile: {}", c),
+            ' ' => Some(Tile::Off),
+            '.' => Some(Tile::Empty),
+            '#' => Some(Tile::Wall),
+            _ => None,
         }
     }
 }
@@ -48,34 +51,13 @@ impl Dir {
     }
 }
 
-fn parse_inputs(s: &str) -> Vec<Input> {
-    let mut out = vec![];
-    let mut temp_num = String::new();
-
-    for c in s.chars() {
-        if c.is_digit(10) {
-            temp_num.push(c);
-        } else {
-            if let Ok(n) = temp_num.parse::<usize>() {
-                out.push(Input::Fwd(n));
-            }
-            let d = match c {
-                'L' => Input::Left,
-                'R' => Input::Right,
-                c => panic!("{} is not L/R", c),
-            };
-            out.push(d);
-            temp_num.clear();
-        }
-    }
-
-    // Parse the final number, if there is one
-    if !temp_num.is_empty() {
-        if let Ok(n) = temp_num.parse::<usize>() {
-            out.push(Input::Fwd(n));
-        }
-    }
-    out
+/// Parses the path, like `10R5L5`.
+fn inputs<'a>() -> impl Parser<'a, Vec<Input>> {
+    let turn = tag("L").or(tag("R")).map(|turn| match turn {
+        "L" => Input::Left,
+        _ => Input::Right,
+    });
+    uint().map(Input::Fwd).or(turn).many1()
 }
 
 fn line(pos: (isize, isize), dir: Dir, len: usize) -> impl Iterator<Item = (isize, isize)> {
@@ -87,7 +69,7 @@ fn line(pos: (isize, isize), dir: Dir, len: usize) -> impl Iterator<Item = (isiz
     })
 }
 
-fn is_on_map(pos: (isize, isize), map: &Vec<Vec<Tile>>) -> bool {
+fn is_on_map(pos: (isize, isize), map: &[Vec<Tile>]) -> bool {
     let ret = pos.1 >= 0
         && pos.0 >= 0
         && (pos.1 as usize) < map.len()
@@ -96,7 +78,7 @@ fn is_on_map(pos: (isize, isize), map: &Vec<Vec<Tile>>) -> bool {
     ret
 }
 
-fn wrap(pos: (isize, isize), dir: Dir, map: &Vec<Vec<Tile>>) -> (usize, usize) {
+fn wrap(pos: (isize, isize), dir: Dir, map: &[Vec<Tile>]) -> (usize, usize) {
     // No need to wrap if the coordinate is on the map
     if is_on_map(pos, map) {
         return (pos.0 as usize, pos.1 as usize);
@@ -130,7 +112,7 @@ fn _print_on_wrap(co: (isize, isize), x: usize, y: usize) {
     }
 }
 
-fn get_pos(pos: (usize, usize), dir: Dir, len: usize, map: &Vec<Vec<Tile>>) -> (usize, usize) {
+fn get_pos(pos: (usize, usize), dir: Dir, len: usize, map: &[Vec<Tile>]) -> (usize, usize) {
     let mut latest_ok = pos;
 
     // Inspect every point in hypothetical line
@@ -176,7 +158,7 @@ fn sim(
     mut pos: (usize, usize),
     mut dir: Dir,
     inputs: &[Input],
-    map: &Vec<Vec<Tile>>,
+    map: &[Vec<Tile>],
 ) -> ((usize, usize), Dir) {
     for input in inputs {
         match input {
@@ -187,26 +169,26 @@ fn sim(
     (pos, dir)
 }
 
-fn main() -> anyhow::Result<()> {
-    let (map_str, input_str) = INPUT.split_once("\n\n").unwrap();
-    let map: Vec<Vec<Tile>> = map_str
-        .lines()
-        .map(|line| line.chars().map(|c| Tile::from(c)).collect_vec())
-        .collect();
-
-    let inputs = parse_inputs(input_str.trim());
+fn parse(input: &str) -> anyhow::Result<(Vec<Vec<Tile>>, Vec<Input>)> {
+    let map = grid("' ', '.' or '#'", Tile::from_char);
+    Ok(parse_all(map.skip(tag("\n\n")).then(inputs()), input)?)
+}
 
+fn part1(map: &[Vec<Tile>], inputs: &[Input]) -> usize {
     let init_pos = (
         map[0].iter().position(|tile| tile == &Tile::Empty).unwrap(),
         0,
     );
     let init_dir = Dir::Right;
 
-    let ((ox, oy), ofacing) = sim(init_pos, init_dir, &inputs, &map);
+    let ((ox, oy), ofacing) = sim(init_pos, init_dir, inputs, map);
     let (ocol, orow) = (ox + 1, oy + 1);
 
-    let part1 = 1000 * orow + 4 * ocol + ofacing as usize;
+    1000 * orow + 4 * ocol + ofacing as usize
+}
 
-    println!("Part 1: {}", part1);
-    Ok(())
+fn main() -> anyhow::Result<()> {
+    Day::new(22, INPUT, parse)
+        .part1("wrap", |(map, inputs)| part1(map, inputs))
+        .main()
 }
@@ -1,37 +1,49 @@
+use anyhow::{bail, ensure};
+use aoc::{
+    bitset::BitSet,
+    parse::{grid, parse_all},
+    Day,
+};
 use itertools::Itertools;
-use std::{collections::VecDeque, iter};
 
 const INPUT: &str = include_str!("inputs/day24.txt");
 
-fn _print_blizzards(blizz: &Vec<Blizzard>, w: usize, h: usize) {
+#[cfg_attr(not(test), allow(dead_code))]
+fn render_blizzards(blizz: &[Blizzard], w: usize, h: usize) -> String {
+    let mut out = String::new();
     for _ in 1..w + 1 {
-        print!("#");
+        out.push('#');
     }
-    println!();
+    out.push('\n');
     for y in 1..h - 1 {
-        print!("#");
+        out.push('#');
         for x in 1..w - 1 {
             let co = (x, y);
             let bcount = blizz.iter().filter(|b| b.pos == co).count();
             if bcount > 1 {
-                print!("{}", bcount);
+                out.push_str(&bcount.to_string());
             } else if let Some(b) = blizz.iter().find(|b| b.pos == co) {
-                match b.dir {
-                    Dir::Right => print!(">"),
-                    Dir::Left => print!("<"),
-                    Dir::Up => print!("^"),
-                    Dir::Down => print!("v"),
-                }
+                out.push(match b.dir {
+                    Dir::Right => '>',
+                    Dir::Left => '<',
+                    Dir::Up => '^',
+                    Dir::Down => 'v',
+                });
             } else {
-                print!(".");
+                out.push('.');
             }
         }
-        println!("#");
+        out.push_str("#\n");
     }
     for _ in 1..w + 1 {
-        print!("#");
+        out.push('#');
     }
-    println!();
+    out.push('\n');
+    out
+}
+
+fn _print_blizzards(blizz: &[Blizzard], w: usize, h: usize) {
+    print!("{}", render_blizzards(blizz, w, h));
 }
 
 #[derive(Clone)]
@@ -88,125 +100,121 @@ fn next_blizzards(blizzards: &[Blizzard], w: usize, h: usize) -> Vec<Blizzard> {
         .collect_vec()
 }
 
-fn available<'b>(
+/// Returns the cells taken by blizzards, as indices `y * w + x`
+fn occupied(blizzards: &[Blizzard], w: usize) -> BitSet {
+    blizzards.iter().map(|b| b.pos.1 * w + b.pos.0).collect()
+}
+
+/// Returns the cells the expedition can be in a minute after being at `pos`,
+/// before checking for blizzards
+fn available(
     pos: (usize, usize),
-    blizzards: &'b [Blizzard],
+    start: (usize, usize),
+    dest: (usize, usize),
     w: usize,
     h: usize,
-) -> impl Iterator<Item = (usize, usize)> + 'b + Clone {
+) -> impl Iterator<Item = (usize, usize)> {
     let (x, y) = pos;
-
-    let xr = match x {
-        0 => x + 1,
-        1 => x,
-        _ => x - 1,
-    }..=if x == w - 1 {
-        x - 1
-    } else if x == w - 2 {
-        x
-    } else {
-        x + 1
-    };
-    let yr = match y {
-        0 => y + 1,
-        1 => y,
-        _ => y - 1,
-    }..=if y == h - 1 {
-        y - 1
-    } else if y == h - 2 {
-        y
-    } else {
-        y + 1
-    };
-
-    xr.cartesian_product(yr)
-        .filter(move |co| !blizzards.iter().any(|b| b.pos == *co))
+    let moves = [
+        Some((x, y)),
+        Some((x + 1, y)),
+        x.checked_sub(1).map(|x| (x, y)),
+        Some((x, y + 1)),
+        y.checked_sub(1).map(|y| (x, y)),
+    ];
+    moves.into_iter().flatten().filter(move |&co| {
+        let inside = (1..w - 1).contains(&co.0) && (1..h - 1).contains(&co.1);
+        inside || co == start || co == dest
+    })
 }
 
+/// Returns the number of minutes it takes to get from `start` to `dest`,
+/// tracking every cell the expedition could be in at each minute.
+///
+/// The blizzards come back every lcm(w - 2, h - 2) minutes, and the
+/// expedition can always wait at the start, so the cells it could be in a
+/// period later include those it could be in now. Once none of them grew
+/// for a whole period, no more ever will, and `dest` can't be reached.
 fn route_bfs(
     start: (usize, usize),
     dest: (usize, usize),
     mut blizz: Vec<Blizzard>,
     w: usize,
     h: usize,
-) -> (usize, Vec<(usize, usize)>, Vec<Vec<Blizzard>>) {
-    let mut q = VecDeque::new();
-
-    blizz = next_blizzards(&blizz, w, h);
-    q.extend(
-        available(start, &blizz, w, h)
-            .chain(iter::once(start))
-            .map(|co| (1, co, vec![start], vec![blizz.clone()]))
-            .collect_vec(),
-    );
+) -> anyhow::Result<usize> {
+    ensure!(w > 2 && h > 2, "the valley has no room inside its walls");
+    let period = (1..=h - 2)
+        .map(|k| k * (w - 2))
+        .find(|m| m % (h - 2) == 0)
+        .unwrap();
+    // The number of cells the expedition could be in a period earlier, by
+    // minute modulo the period
+    let mut before = vec![0; period];
+    let mut unchanged = 0;
 
-    let mut pdepth = 0;
+    let mut frontier = BitSet::new();
+    frontier.insert(start.1 * w + start.0);
 
-    while let Some((depth, co, hist, hist_b)) = q.pop_front() {
-        // Check if we are one-off from destination
-        if co.0 >= dest.0 - 1 && co.0 <= dest.0 + 1 && co.1 + 1 == dest.1 {
-            return (depth + 1, hist, hist_b);
-        }
+    let mut minute = 0;
+    while unchanged < period {
+        minute += 1;
+        blizz = next_blizzards(&blizz, w, h);
+        let taken = occupied(&blizz, w);
 
-        // If we are starting to analyze the next depth, increment blizzards
-        if depth > pdepth {
-            blizz = next_blizzards(&blizz, w, h);
-            pdepth = depth;
+        let mut next = BitSet::with_capacity(w * h);
+        for cell in frontier.iter() {
+            for co in available((cell % w, cell / w), start, dest, w, h) {
+                if co == dest {
+                    return Ok(minute);
+                }
+                next.insert(co.1 * w + co.0);
+            }
         }
+        next.difference_with(&taken);
+        frontier = next;
 
-        // Check available moves and append them to queue
-        q.extend(
-            available(co, &blizz, w, h)
-                .map(|co| {
-                    (
-                        depth + 1,
-                        co,
-                        {
-                            let mut h = hist.clone();
-                            h.push(co);
-                            h
-                        },
-                        {
-                            let mut b = hist_b.clone();
-                            b.push(blizz.clone());
-                            b
-                        },
-                    )
-                })
-                .collect_vec(),
-        );
+        let earlier = std::mem::replace(&mut before[minute % period], frontier.len());
+        unchanged = if earlier == frontier.len() {
+            unchanged + 1
+        } else {
+            0
+        };
     }
-    (0, vec![], vec![])
+    bail!("there is no way from {start:?} to {dest:?} past the blizzards")
 }
 
-fn main() -> anyhow::Result<()> {
-    let mut blizzards = vec![];
-    let walls = INPUT
-        .lines()
-        .enumerate()
-        .map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(x, c)| match c {
-                    '#' => true,
-                    '.' => false,
-                    b @ ('>' | 'v' | '^' | '<') => {
-                        use Dir::*;
-                        let dir = match b {
-                            '>' => Right,
-                            '<' => Left,
-                            '^' => Up,
-                            'v' => Down,
-                            c => panic!("unrecognized char in input: {}", c),
-                        };
-                        blizzards.push(Blizzard { pos: (x, y), dir });
-                        false
-                    }
-                    c => panic!("unrecognized char in input: {}", c),
-                })
-                .collect_vec()
-        })
+struct Valley {
+    blizzards: Vec<Blizzard>,
+    w: usize,
+    h: usize,
+    expedition: (usize, usize),
+    dest: (usize, usize),
+}
+
+fn parse(input: &str) -> anyhow::Result<Valley> {
+    // Whether each cell is a wall, and the blizzard in it, if any
+    let cells = grid("'#', '.' or a blizzard", |c| match c {
+        '#' => Some((true, None)),
+        '.' => Some((false, None)),
+        '>' => Some((false, Some(Dir::Right))),
+        '<' => Some((false, Some(Dir::Left))),
+        '^' => Some((false, Some(Dir::Up))),
+        'v' => Some((false, Some(Dir::Down))),
+        _ => None,
+    });
+    let cells = parse_all(cells, input)?;
+    let walls = cells
+        .iter()
+        .map(|row| row.iter().map(|&(wall, _)| wall).collect_vec())
         .collect_vec();
+    let mut blizzards = vec![];
+    for (y, row) in cells.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if let (_, Some(dir)) = *cell {
+                blizzards.push(Blizzard { pos: (x, y), dir });
+            }
+        }
+    }
 
     let (w, h) = (walls[0].len(), walls.len());
     let expedition = (walls[0].iter().position(|&wall| !wall).unwrap(), 0usize);
@@ -217,14 +225,69 @@ fn main() -> anyhow::Result<()> {
             .unwrap(),
         walls.len() - 1,
     );
-    let route_len = route_bfs(expedition, dest, blizzards, w, h);
-    for (min, (co, bliz)) in route_len.1.iter().zip(route_len.2.iter()).enumerate() {
-        println!("Minute {}, {:?}", min, co);
-        println!("Next bliz:");
-        _print_blizzards(bliz, w, h);
-        println!();
+
+    Ok(Valley {
+        blizzards,
+        w,
+        h,
+        expedition,
+        dest,
+    })
+}
+
+#[test]
+fn valley_snapshot() {
+    let Valley {
+        mut blizzards,
+        w,
+        h,
+        ..
+    } = parse(include_str!("inputs/day24-small.txt")).unwrap();
+
+    let mut out = String::new();
+    for minute in 0..=5 {
+        out.push_str(&format!("Minute {minute}\n"));
+        out.push_str(&render_blizzards(&blizzards, w, h));
+        blizzards = next_blizzards(&blizzards, w, h);
     }
-    println!("Part 1: {}", route_len.0);
+    aoc::assert_snapshot!("day24-small-valley", out);
+}
 
-    Ok(())
+#[test]
+fn small_example() {
+    let valley = parse(include_str!("inputs/day24-small.txt")).unwrap();
+    let minutes = route_bfs(
+        valley.expedition,
+        valley.dest,
+        valley.blizzards,
+        valley.w,
+        valley.h,
+    );
+    assert_eq!(minutes.unwrap(), 18);
+
+    // The blizzards in the last column always cover the cell before the exit
+    let valley = parse("#.###\n#..v#\n#..v#\n###.#").unwrap();
+    let minutes = route_bfs(
+        valley.expedition,
+        valley.dest,
+        valley.blizzards,
+        valley.w,
+        valley.h,
+    );
+    assert!(minutes.is_err());
+}
+
+fn main() -> anyhow::Result<()> {
+    Day::new(24, INPUT, parse)
+        .part1("bfs", |valley| {
+            let Valley {
+                blizzards,
+                w,
+                h,
+                expedition,
+                dest,
+            } = valley;
+            route_bfs(*expedition, *dest, blizzards.clone(), *w, *h)
+        })
+        .main()
 }
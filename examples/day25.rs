@@ -0,0 +1,135 @@
+use anyhow::Context;
+use aoc::{
+    numeral::{Numeral, System},
+    Day,
+};
+use std::{fmt, iter::Sum, ops::Add, str::FromStr};
+
+const INPUT: &str = include_str!("inputs/day25.txt");
+
+/// Base 5 with the digits `=`, `-`, `0`, `1` and `2` for -2 to 2
+struct SnafuDigits;
+
+impl System for SnafuDigits {
+    const SYMBOLS: &'static str = "=-012";
+    const LOWEST: i32 = -2;
+    const RADICES: &'static [u32] = &[5];
+}
+
+/// A number in SNAFU notation, of any size.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Snafu(Numeral<SnafuDigits>);
+
+impl FromStr for Snafu {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Snafu(s.parse().context("invalid SNAFU number")?))
+    }
+}
+
+impl fmt::Display for Snafu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Add for Snafu {
+    type Output = Snafu;
+
+    fn add(self, rhs: Snafu) -> Snafu {
+        Snafu(self.0 + rhs.0)
+    }
+}
+
+impl Sum for Snafu {
+    fn sum<I: Iterator<Item = Snafu>>(iter: I) -> Snafu {
+        Snafu(iter.map(|n| n.0).sum())
+    }
+}
+
+impl From<i128> for Snafu {
+    fn from(n: i128) -> Snafu {
+        Snafu(Numeral::from_int(n).expect("balanced digits write any integer"))
+    }
+}
+
+impl From<i64> for Snafu {
+    fn from(n: i64) -> Snafu {
+        Snafu::from(n as i128)
+    }
+}
+
+impl TryFrom<Snafu> for i128 {
+    type Error = anyhow::Error;
+
+    fn try_from(n: Snafu) -> anyhow::Result<i128> {
+        n.0.to_int()
+            .with_context(|| format!("{n} doesn't fit in 128 bits"))
+    }
+}
+
+impl TryFrom<Snafu> for i64 {
+    type Error = anyhow::Error;
+
+    fn try_from(n: Snafu) -> anyhow::Result<i64> {
+        let wide = i128::try_from(n.clone())?;
+        wide.try_into()
+            .with_context(|| format!("{n} doesn't fit in 64 bits"))
+    }
+}
+
+fn parse(input: &str) -> anyhow::Result<Vec<Snafu>> {
+    input.lines().map(str::parse).collect()
+}
+
+#[test]
+fn example() {
+    let numbers = parse(include_str!("inputs/day25-example.txt")).unwrap();
+    assert_eq!(i128::try_from(numbers[0].clone()).unwrap(), 1747);
+    assert_eq!(numbers.into_iter().sum::<Snafu>().to_string(), "2=-1=0");
+    assert!("1=3".parse::<Snafu>().is_err());
+    assert!("".parse::<Snafu>().is_err());
+    for n in [i128::MIN, i128::MAX] {
+        let snafu = Snafu::from(n);
+        assert_eq!(snafu.to_string().parse::<Snafu>().unwrap(), snafu);
+    }
+
+    // Sums past 128 bits still work
+    let big = "2".repeat(60).parse::<Snafu>().unwrap();
+    assert!(i128::try_from(big.clone()).is_err());
+    assert_eq!(
+        (big.clone() + big).to_string(),
+        format!("1{}-", "0".repeat(59))
+    );
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn round_trips_numbers(n: i128) {
+        let snafu = Snafu::from(n);
+        proptest::prop_assert_eq!(snafu.to_string().parse::<Snafu>().unwrap(), snafu.clone());
+        proptest::prop_assert_eq!(i128::try_from(snafu).unwrap(), n);
+    }
+
+    #[test]
+    fn round_trips_numerals(s in "[-=12][-=012]{0,40}") {
+        proptest::prop_assert_eq!(s.parse::<Snafu>().unwrap().to_string(), s);
+    }
+
+    #[test]
+    fn adds_like_integers(a: i64, b: i64) {
+        let sum = Snafu::from(a) + Snafu::from(b);
+        proptest::prop_assert_eq!(i128::try_from(sum.clone()).unwrap(), a as i128 + b as i128);
+        proptest::prop_assert_eq!(i64::try_from(sum).ok(), a.checked_add(b));
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    Day::new(25, INPUT, parse)
+        .part1("sum", |numbers| {
+            numbers.iter().cloned().sum::<Snafu>().to_string()
+        })
+        .main()
+}
@@ -1,61 +1,467 @@
-use array_tool::vec::Intersect;
+use anyhow::{bail, ensure, Context};
+use aoc::{
+    bitset::Bits64,
+    runner::{self, Options},
+    Day,
+};
+use itertools::Itertools;
+use std::{collections::HashMap, fmt, str::FromStr};
 
 const INPUT: &str = include_str!("inputs/day3.txt");
 
-fn part1() {
-    let sacks: Vec<(Vec<char>, Vec<char>)> = INPUT
-        .lines()
-        .map(|line| {
-            let (l, r) = line.split_at(line.len() / 2);
-            (l.chars().collect(), r.chars().collect())
-        })
-        .collect();
-
-    let priorities: Vec<(Vec<u32>, Vec<u32>)> = sacks
-        .into_iter()
-        .map(|(l, r)| {
-            (
-                l.into_iter().map(|c| char_into_prio(c)).collect(),
-                r.into_iter().map(|c| char_into_prio(c)).collect(),
-            )
+/// The number of elves that share a badge, unless `--group` says otherwise
+const GROUP: usize = 3;
+
+/// The items in the two compartments of a rucksack, as masks with bit `p`
+/// set for each item of priority `p`, from 1 to 52.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Rucksack {
+    left: Bits64,
+    right: Bits64,
+}
+
+impl FromStr for Rucksack {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Rucksack> {
+        ensure!(
+            s.len().is_multiple_of(2),
+            "{s:?} doesn't split into two compartments"
+        );
+        let (left, right) = s.split_at(s.len() / 2);
+        let items = |items: &str| items.chars().map(prio).collect::<anyhow::Result<_>>();
+        Ok(Rucksack {
+            left: items(left)?,
+            right: items(right)?,
         })
-        .collect();
+    }
+}
 
-    let total_duplicate_prios = priorities
-        .iter()
-        .map(|(l, r)| l.intersect(r.to_vec()).iter().sum::<u32>())
-        .sum::<u32>();
+impl Rucksack {
+    fn items(&self) -> Bits64 {
+        self.left | self.right
+    }
+
+    /// Returns the priority of the item in both compartments.
+    fn misplaced(&self) -> anyhow::Result<usize> {
+        only(self.left & self.right).context("the compartments")
+    }
+}
+
+/// Returns the priority of item `c`.
+fn prio(c: char) -> anyhow::Result<usize> {
+    match c {
+        'a'..='z' => Ok(c as usize - 'a' as usize + 1),
+        'A'..='Z' => Ok(c as usize - 'A' as usize + 27),
+        _ => bail!("invalid item {c:?}"),
+    }
+}
 
-    println!("{total_duplicate_prios}");
+/// Returns the item of priority `p`.
+fn item(p: usize) -> char {
+    let mut letters = ('a'..='z').chain('A'..='Z');
+    letters.nth(p - 1).expect("priorities go from 1 to 52")
 }
 
-fn part2() {
-    let sacks: Vec<Vec<char>> = INPUT.lines().map(|line| line.chars().collect()).collect();
-    let badges: Vec<char> = sacks
-        .chunks(3)
-        .map(|group| {
-            group[0]
-                .intersect(group[1].to_vec())
-                .intersect(group[2].to_vec())[0]
+/// Returns the priority of the one item in `common`.
+fn only(common: Bits64) -> anyhow::Result<usize> {
+    match common.len() {
+        1 => Ok(common.first().unwrap()),
+        0 => bail!("share no item"),
+        _ => bail!("share {}", common.iter().map(item).join(", ")),
+    }
+}
+
+/// Returns the badges of the groups of `size` elves, in order.
+fn badges(sacks: &[Rucksack], size: usize) -> anyhow::Result<Vec<usize>> {
+    ensure!(
+        size > 0 && sacks.len().is_multiple_of(size),
+        "{} rucksacks don't make groups of {size}",
+        sacks.len()
+    );
+    sacks
+        .chunks(size)
+        .enumerate()
+        .map(|(idx, group)| {
+            let common = group.iter().map(Rucksack::items).reduce(|a, b| a & b);
+            only(common.unwrap()).with_context(|| format!("the rucksacks of group {}", idx + 1))
         })
-        .collect();
+        .collect()
+}
 
-    let total_prio = badges.into_iter().map(|b| char_into_prio(b)).sum::<u32>();
+fn part1(sacks: &[Rucksack]) -> anyhow::Result<usize> {
+    sacks.iter().enumerate().try_fold(0, |sum, (idx, sack)| {
+        let prio = sack.misplaced();
+        Ok(sum + prio.with_context(|| format!("rucksack {}", idx + 1))?)
+    })
+}
 
-    println!("{total_prio}");
+fn part2(sacks: &[Rucksack], group: usize) -> anyhow::Result<usize> {
+    Ok(badges(sacks, group)?.iter().sum())
 }
 
-fn char_into_prio(c: char) -> u32 {
-    if c.is_lowercase() {
-        (c as u32) - ('a' as u32) + 1
-    } else {
-        (c.to_ascii_lowercase() as u32) - ('a' as u32) + 27
+/// A compartment of a rucksack
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    fn other(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    part1();
-    part2();
+/// How many items of each priority a rucksack holds in each compartment
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Counts([[usize; 53]; 2]);
+
+impl FromStr for Counts {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Counts> {
+        s.parse::<Rucksack>()?;
+        let (left, right) = s.split_at(s.len() / 2);
+        let mut counts = [[0; 53]; 2];
+        for (side, items) in [left, right].into_iter().enumerate() {
+            for c in items.chars() {
+                counts[side][prio(c)?] += 1;
+            }
+        }
+        Ok(Counts(counts))
+    }
+}
 
-    Ok(())
+impl Counts {
+    fn get(&self, side: Side, prio: usize) -> usize {
+        self.0[side as usize][prio]
+    }
+
+    fn has(&self, prio: usize) -> bool {
+        self.get(Side::Left, prio) + self.get(Side::Right, prio) > 0
+    }
+
+    fn shared(&self, prio: usize) -> bool {
+        self.get(Side::Left, prio) > 0 && self.get(Side::Right, prio) > 0
+    }
+
+    /// The compartment that keeps an item when it has to be in one: the one
+    /// holding more of it, or the left one
+    fn keeper(&self, prio: usize) -> Side {
+        if self.get(Side::Right, prio) > self.get(Side::Left, prio) {
+            Side::Right
+        } else {
+            Side::Left
+        }
+    }
+}
+
+/// Moving `count` items of priority `prio` from a compartment to another,
+/// each given by the index of its rucksack and its side
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Move {
+    prio: usize,
+    count: usize,
+    from: (usize, Side),
+    to: (usize, Side),
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let place = |(sack, side): (usize, Side)| format!("rucksack {} {side:?}", sack + 1);
+        write!(
+            f,
+            "{} × {} from {} to {}",
+            self.count,
+            item(self.prio),
+            place(self.from),
+            place(self.to)
+        )
+    }
+}
+
+/// The moves that leave no item in both compartments of a rucksack and one
+/// badge per group, with the priorities before and after.
+#[derive(Debug)]
+struct Plan {
+    moves: Vec<Move>,
+    /// The sum of the priorities of the items in both compartments
+    misplaced: [usize; 2],
+    /// The sum of the priorities of the items common to each group
+    badges: [usize; 2],
+}
+
+impl Plan {
+    fn items(&self) -> usize {
+        self.moves.iter().map(|m| m.count).sum()
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for m in &self.moves {
+            writeln!(f, "Move {m}")?;
+        }
+        writeln!(f, "{} items in {} moves", self.items(), self.moves.len())?;
+        for (name, [before, after]) in [("Misplaced", self.misplaced), ("Badge", self.badges)] {
+            let saving = before - after;
+            writeln!(f, "{name} priorities {before} -> {after}, saving {saving}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the sums of the misplaced and of the badge priorities.
+fn priorities(sacks: &[Counts], group: usize) -> [usize; 2] {
+    let misplaced = sacks
+        .iter()
+        .flat_map(|sack| (1..=52).filter(|&p| sack.shared(p)))
+        .sum();
+    let badges = sacks
+        .chunks(group)
+        .flat_map(|members| (1..=52).filter(|&p| members.iter().all(|sack| sack.has(p))))
+        .sum();
+    [misplaced, badges]
+}
+
+/// Plans the fewest item moves that leave no item in both compartments of a
+/// rucksack and exactly one item common to each group of `group` rucksacks.
+///
+/// Sorting out an item shared by the compartments moves the copies on the
+/// side with fewer. Each common item but the badge has to leave a rucksack
+/// of the group, which moves all its copies there instead, so costs the
+/// copies on the side with more on top. Evicting each item from the rucksack
+/// where this costs the least, and keeping the badge that would cost the
+/// most, gives the fewest moves.
+fn plan(sacks: &[Counts], group: usize) -> anyhow::Result<Plan> {
+    ensure!(
+        group > 0 && sacks.len().is_multiple_of(group),
+        "{} rucksacks don't make groups of {group}",
+        sacks.len()
+    );
+
+    // The rucksack each evicted item goes to, by rucksack and priority
+    let mut evicted = HashMap::new();
+    for start in (0..sacks.len()).step_by(group) {
+        let members = start..start + group;
+        let eviction = |p: usize| {
+            let cost = |i: usize| {
+                sacks[i]
+                    .get(Side::Left, p)
+                    .max(sacks[i].get(Side::Right, p))
+            };
+            members.clone().map(|i| (cost(i), i)).min().unwrap()
+        };
+        let common = (1..=52).filter(|&p| members.clone().all(|i| sacks[i].has(p)));
+        let badge = common
+            .clone()
+            .max_by_key(|&p| eviction(p).0)
+            .with_context(|| {
+                let group = start / group + 1;
+                format!("the rucksacks of group {group} share no item to keep as a badge")
+            })?;
+        for p in common.filter(|&p| p != badge) {
+            let from = eviction(p).1;
+            let to = members.clone().find(|&i| i != from).with_context(|| {
+                let group = start / group + 1;
+                format!("group {group} has no other rucksack to move {} to", item(p))
+            })?;
+            evicted.insert((from, p), to);
+        }
+    }
+
+    let mut moves = vec![];
+    for (i, sack) in sacks.iter().enumerate() {
+        for p in 1..=52 {
+            if let Some(&to) = evicted.get(&(i, p)) {
+                for side in [Side::Left, Side::Right] {
+                    let count = sack.get(side, p);
+                    if count > 0 {
+                        let to = (to, sacks[to].keeper(p));
+                        moves.push(Move {
+                            prio: p,
+                            count,
+                            from: (i, side),
+                            to,
+                        });
+                    }
+                }
+            } else if sack.shared(p) {
+                let keeper = sack.keeper(p);
+                moves.push(Move {
+                    prio: p,
+                    count: sack.get(keeper.other(), p),
+                    from: (i, keeper.other()),
+                    to: (i, keeper),
+                });
+            }
+        }
+    }
+
+    let mut after = sacks.to_vec();
+    for m in &moves {
+        after[m.from.0].0[m.from.1 as usize][m.prio] -= m.count;
+        after[m.to.0].0[m.to.1 as usize][m.prio] += m.count;
+    }
+    let (before, after) = (priorities(sacks, group), priorities(&after, group));
+    Ok(Plan {
+        moves,
+        misplaced: [before[0], after[0]],
+        badges: [before[1], after[1]],
+    })
+}
+
+impl From<&Counts> for Rucksack {
+    fn from(counts: &Counts) -> Rucksack {
+        let items = |side| (1..=52).filter(|&p| counts.get(side, p) > 0).collect();
+        Rucksack {
+            left: items(Side::Left),
+            right: items(Side::Right),
+        }
+    }
+}
+
+/// The rucksacks, as the masks of their items that the parts need and as
+/// the counts of their items that the plan needs
+struct Rucksacks {
+    masks: Vec<Rucksack>,
+    counts: Vec<Counts>,
+}
+
+fn parse(input: &str) -> anyhow::Result<Rucksacks> {
+    let counts = input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| line.parse().with_context(|| format!("line {}", idx + 1)))
+        .collect::<anyhow::Result<Vec<Counts>>>()?;
+    let masks = counts.iter().map(Rucksack::from).collect();
+    Ok(Rucksacks { masks, counts })
+}
+
+#[test]
+fn example() {
+    let sacks = parse(
+        "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw",
+    )
+    .unwrap()
+    .masks;
+    assert_eq!(part1(&sacks).unwrap(), 157);
+    assert_eq!(
+        badges(&sacks, 3).unwrap(),
+        [prio('r').unwrap(), prio('Z').unwrap()]
+    );
+    assert_eq!(part2(&sacks, GROUP).unwrap(), 70);
+
+    // Every rucksack is a group of one, whose badge is any of its items
+    let err = format!("{:#}", badges(&sacks, 1).unwrap_err());
+    let items = "c, f, g, h, p, r, s, t, v, w, F, J, M, W";
+    assert_eq!(err, format!("the rucksacks of group 1: share {items}"));
+    assert!(badges(&sacks, 4).is_err());
+    assert!(badges(&sacks, 0).is_err());
+
+    let err = part1(&parse("abcd\nabca").unwrap().masks).unwrap_err();
+    assert_eq!(
+        format!("{err:#}"),
+        "rucksack 1: the compartments: share no item"
+    );
+    assert!(parse("abc").is_err());
+    assert!(parse("a1").is_err());
+}
+
+#[test]
+fn plans_fewest_moves() {
+    let counts = |text: &str| parse(text).unwrap().counts;
+
+    // Each rucksack of the input has one misplaced item, and each group one
+    // badge
+    let input = counts(INPUT);
+    let sorted = plan(&input, GROUP).unwrap();
+    assert_eq!(sorted.moves.len(), input.len());
+    assert_eq!(sorted.misplaced, [7691, 0]);
+    assert_eq!(sorted.badges, [2508, 2508]);
+
+    // The group shares a and b, and a costs less to evict
+    let sacks = counts("abbcde\nabbfgh\nabbxyb");
+    let sorted = plan(&sacks, 3).unwrap();
+    let moves = sorted
+        .moves
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        moves,
+        [
+            "1 × a from rucksack 1 Left to rucksack 2 Left",
+            "1 × b from rucksack 3 Right to rucksack 3 Left",
+        ]
+    );
+    assert_eq!((sorted.misplaced, sorted.badges), ([2, 0], [3, 2]));
+
+    assert!(plan(&counts("ab\ncd"), 2).is_err());
+    // A group of one can keep only its badge, and has nowhere to put the
+    // other items
+    let alone = plan(&counts("aa\nbb"), 1).unwrap();
+    assert_eq!((alone.misplaced, alone.badges), ([3, 0], [3, 3]));
+    let err = plan(&counts("abab"), 1).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "group 1 has no other rucksack to move a to"
+    );
+    assert!(plan(&sacks, 2).is_err());
+}
+
+/// The day for groups of `group` elves
+fn day(group: usize) -> anyhow::Result<Day<Rucksacks>> {
+    ensure!(group > 0, "a group needs at least one elf");
+    let day = Day::new(3, INPUT, parse)
+        .assume("the rucksacks split into whole groups", move |sacks| {
+            let n = sacks.masks.len();
+            ensure!(
+                n.is_multiple_of(group),
+                "{n} rucksacks don't make groups of {group}"
+            );
+            Ok(())
+        })
+        .part1("mask", |sacks| part1(&sacks.masks))
+        .part2("mask", move |sacks| part2(&sacks.masks, group))
+        .mode(
+            "plan",
+            None,
+            "print the moves that sort out the rucksacks",
+            move |sacks, _| Ok(plan(&sacks.counts, group)?.to_string()),
+        );
+    Ok(day)
+}
+
+#[test]
+fn takes_any_group_size() {
+    let input = "ab\nac\nbd\ncd";
+    let pairs = day(2).unwrap();
+    let sacks = pairs.parse(input).unwrap();
+    assert_eq!(pairs.solvers(2)[0].solve(&sacks).unwrap(), "5");
+    assert!(day(3).unwrap().parse(input).is_err());
+    assert!(day(0).is_err());
+}
+
+/// Takes the runner's options, and `--group SIZE` for the number of elves
+/// that share a badge.
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let group = match runner::take_value(&mut args, "--group")? {
+        Some(size) => size
+            .parse()
+            .with_context(|| format!("invalid group size: {size}"))?,
+        None => GROUP,
+    };
+    let day = day(group)?;
+    runner::run(&day, &Options::parse_for(&day, args)?)
 }
@@ -1,81 +1,44 @@
-use std::ops::RangeInclusive;
-
-use itertools::Itertools;
+use aoc::{
+    interval::Interval,
+    parse::{lines, parse_all, range, tag, Parser},
+    Day,
+};
 
 const INPUT: &str = include_str!("inputs/day4.txt");
 
-fn part1(pairs: &[(RangeInclusive<u32>, RangeInclusive<u32>)]) {
-    let mut count = 0;
+type Sections = Interval<u64>;
 
-    for (l, r) in pairs {
-        let mut l_contains_r = true;
-        let mut r_contains_l = true;
-        for x in r.clone() {
-            if !l.contains(&x) {
-                l_contains_r = false;
-                break;
-            }
-        }
-        for x in l.clone() {
-            if !r.contains(&x) {
-                r_contains_l = false;
-                break;
-            }
-        }
-        if l_contains_r || r_contains_l {
-            count += 1;
-        }
-    }
+fn part1(pairs: &[(Sections, Sections)]) -> usize {
+    pairs
+        .iter()
+        .filter(|(l, r)| l.is_superset(r) || r.is_superset(l))
+        .count()
+}
 
-    println!("{count}");
+fn part2(pairs: &[(Sections, Sections)]) -> usize {
+    pairs.iter().filter(|(l, r)| l.overlaps(r)).count()
 }
 
-fn part2(pairs: &[(RangeInclusive<u32>, RangeInclusive<u32>)]) {
-    let mut count = 0;
+fn parse(input: &str) -> anyhow::Result<Vec<(Sections, Sections)>> {
+    let sections = || range().try_map("a range of sections", |r| Interval::try_from(r).ok());
+    let pair = sections().skip(tag(",")).then(sections());
+    Ok(parse_all(lines(pair), input)?)
+}
 
-    for (l, r) in pairs {
-        let mut l_contains_r = false;
-        let mut r_contains_l = false;
-        for x in r.clone() {
-            if l.contains(&x) {
-                l_contains_r = true;
-                break;
-            }
-        }
-        for x in l.clone() {
-            if r.contains(&x) {
-                r_contains_l = true;
-                break;
-            }
-        }
-        if l_contains_r || r_contains_l {
-            count += 1;
-        }
-    }
+#[test]
+fn example() {
+    let pairs = parse("2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8").unwrap();
+    assert_eq!((part1(&pairs), part2(&pairs)), (2, 4));
 
-    println!("{count}");
+    // Checking a pair costs the same however many sections it spans
+    let huge = parse("0-18446744073709551615,1-18446744073709551614").unwrap();
+    assert_eq!((part1(&huge), part2(&huge)), (1, 1));
+    assert!(parse("4-2,1-1").is_err());
 }
 
 fn main() -> anyhow::Result<()> {
-    let pairs = INPUT
-        .lines()
-        .map(|line| {
-            let mut sections = line.split(',').map(|line| {
-                line.split('-')
-                    .map(|section| section.parse::<u32>().unwrap())
-            });
-            let mut l_sects = sections.next().unwrap();
-            let mut r_sects = sections.next().unwrap();
-
-            let l = l_sects.next().unwrap()..=l_sects.next().unwrap();
-            let r = r_sects.next().unwrap()..=r_sects.next().unwrap();
-
-            (l, r)
-        })
-        .collect_vec();
-
-    part1(&pairs);
-    part2(&pairs);
-
-    Ok(())
+    Day::new(4, INPUT, parse)
+        .part1("interval", |pairs| part1(pairs))
+        .part2("interval", |pairs| part2(pairs))
+        .main()
 }
@@ -1,3 +1,8 @@
+use anyhow::ensure;
+use aoc::{
+    parse::{lines, parse_all, preceded, rest_of_line, tag, uint, Parser},
+    Day,
+};
 use itertools::Itertools;
 
 const INPUT: &str = include_str!("inputs/day5.txt");
@@ -5,31 +10,36 @@ const INPUT: &str = include_str!("inputs/day5.txt");
 #[derive(Clone)]
 struct Stacks(Vec<Vec<char>>);
 
-impl Stacks {
-    fn from_init_lines(lines: &[&str]) -> Self {
-        let mut stacks = vec![Vec::with_capacity(8); 9];
+/// (count, from, to)
+type Command = (usize, usize, usize);
 
-        let lines = lines.iter().rev();
+impl Stacks {
+    /// Reads the drawing of the stacks, with one stack per number in the
+    /// heading at the bottom. Returns `None` if a crate is outside of the
+    /// numbered stacks.
+    fn from_init_lines(lines: &[&str]) -> Option<Self> {
+        let mut lines = lines.iter().rev();
 
-        // Skip the number heading
-        let lines = lines.skip(1).collect_vec();
+        // The number heading
+        let count = lines.next()?.split_whitespace().count();
+        let mut stacks = vec![Vec::with_capacity(8); count];
 
         for line in lines {
             // Break into crates
             for (idx, mut chunk) in line.chars().chunks(4).into_iter().enumerate() {
                 // Pick the characted, which is always 2nd
-                let letter = match chunk.nth(1).unwrap() {
-                    ' ' => None,
-                    l => Some(l),
+                let letter = match chunk.nth(1) {
+                    None | Some(' ') => None,
+                    l => l,
                 };
 
                 if let Some(letter) = letter {
-                    stacks[idx].push(letter);
+                    stacks.get_mut(idx)?.push(letter);
                 }
             }
         }
 
-        Stacks(stacks)
+        Some(Stacks(stacks))
     }
 
     fn move_crate(&mut self, from: usize, to: usize) {
@@ -61,43 +71,79 @@ impl Stacks {
     }
 }
 
-fn part1(mut stacks: Stacks, cmds: &[(usize, usize, usize)]) -> Stacks {
+fn part1(mut stacks: Stacks, cmds: &[Command]) -> Stacks {
     for (n, from, to) in cmds {
         stacks.move_crates_one_by_one(*from, *to, *n);
     }
     stacks
 }
 
-fn part2(mut stacks: Stacks, cmds: &[(usize, usize, usize)]) -> Stacks {
+fn part2(mut stacks: Stacks, cmds: &[Command]) -> Stacks {
     for (n, from, to) in cmds {
         stacks.move_crate_stack(*from, *to, *n);
     }
     stacks
 }
 
-fn main() -> anyhow::Result<()> {
-    let lines = INPUT.lines().collect_vec();
-    let init_lines = &lines[0..9];
-    let cmd_lines = &lines[10..];
-
-    let stacks = Stacks::from_init_lines(init_lines);
-
-    let commands = cmd_lines
-        .iter()
-        .map(|line| {
-            let toks = line.split_ascii_whitespace().collect_vec();
-            let n = toks[1].parse::<usize>().unwrap();
-            let from = toks[3].parse::<usize>().unwrap() - 1;
-            let to = toks[5].parse::<usize>().unwrap() - 1;
-            (n, from, to)
-        })
-        .collect_vec();
-
-    let p1 = part1(stacks.clone(), &commands).top_string();
-    println!("Part 1: {p1}");
+fn parse(input: &str) -> anyhow::Result<(Stacks, Vec<Command>)> {
+    let drawing = lines(rest_of_line()).try_map("a drawing of numbered stacks", |lines| {
+        Stacks::from_init_lines(&lines)
+    });
+    // move 1 from 2 to 1
+    let stack = || uint::<usize>().try_map("a stack number", |n| n.checked_sub(1));
+    let command = preceded(tag("move "), uint())
+        .skip(tag(" from "))
+        .then(stack())
+        .skip(tag(" to "))
+        .then(stack())
+        .map(|((n, from), to)| (n, from, to));
+
+    let puzzle = drawing.skip(tag("\n\n")).then(lines(command));
+    Ok(parse_all(puzzle, input)?)
+}
 
-    let p2 = part2(stacks.clone(), &commands).top_string();
-    println!("Part 2: {p2}");
+fn moves_between_stacks((stacks, cmds): &(Stacks, Vec<Command>)) -> anyhow::Result<()> {
+    for (i, (_, from, to)) in cmds.iter().enumerate() {
+        let count = stacks.0.len();
+        ensure!(
+            *from < count && *to < count,
+            "move {} is from stack {} to {}, but there are {count} stacks",
+            i + 1,
+            from + 1,
+            to + 1
+        );
+    }
+    Ok(())
+}
 
+fn moves_take_existing_crates((stacks, cmds): &(Stacks, Vec<Command>)) -> anyhow::Result<()> {
+    let mut heights = stacks.0.iter().map(Vec::len).collect_vec();
+    for (i, &(n, from, to)) in cmds.iter().enumerate() {
+        let height = heights.get(from).copied().unwrap_or(0);
+        ensure!(
+            height >= n,
+            "move {} takes {n} crates from stack {}, which has {height}",
+            i + 1,
+            from + 1
+        );
+        heights[from] -= n;
+        heights[to] += n;
+    }
     Ok(())
 }
+
+fn main() -> anyhow::Result<()> {
+    Day::new(5, INPUT, parse)
+        .assume("moves are between existing stacks", moves_between_stacks)
+        .assume(
+            "moves take only crates that are there",
+            moves_take_existing_crates,
+        )
+        .part1("one by one", |(stacks, commands)| {
+            part1(stacks.clone(), commands).top_string()
+        })
+        .part2("stack", |(stacks, commands)| {
+            part2(stacks.clone(), commands).top_string()
+        })
+        .main()
+}
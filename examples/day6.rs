@@ -1,9 +1,9 @@
+use aoc::Day;
 use itertools::Itertools;
 
 const INPUT: &str = include_str!("inputs/day6.txt");
 
-fn find_zero_duplicate_window(len: usize) -> usize {
-    let chars = INPUT.chars().collect_vec();
+fn find_zero_duplicate_window(chars: &[char], len: usize) -> usize {
     for (idx, win) in chars.windows(len).enumerate() {
         let mut v = win.iter().collect_vec();
         v.sort();
@@ -16,7 +16,8 @@ fn find_zero_duplicate_window(len: usize) -> usize {
 }
 
 fn main() -> anyhow::Result<()> {
-    println!("Part 1: {}", find_zero_duplicate_window(4));
-    println!("Part 2: {}", find_zero_duplicate_window(14));
-    Ok(())
+    Day::new(6, INPUT, |input| Ok(input.chars().collect_vec()))
+        .part1("sort", |chars| find_zero_duplicate_window(chars, 4))
+        .part2("sort", |chars| find_zero_duplicate_window(chars, 14))
+        .main()
 }
@@ -1,3 +1,4 @@
+use aoc::Day;
 use std::collections::HashMap;
 
 const INPUT: &str = include_str!("inputs/day7.txt");
@@ -192,11 +193,11 @@ impl DTree {
     */
 }
 
-fn main() -> anyhow::Result<()> {
+fn parse(input: &str) -> anyhow::Result<DTree> {
     let mut cur_path = Path::root();
     let mut dtree = DTree::Dir(DDir(Path::root(), HashMap::new()));
 
-    let mut lines = INPUT.lines();
+    let mut lines = input.lines();
     while let Some(next_line) = lines.next() {
         let mut toks = next_line.split_ascii_whitespace();
 
@@ -230,29 +231,37 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
-    let sizes: usize = dtree
+    Ok(dtree)
+}
+
+fn part1(dtree: &DTree) -> usize {
+    dtree
         .dirs()
         .iter()
         .filter(|dir| dir.size() <= 100000)
         .map(|dir| dir.size())
-        .sum();
-    println!("Part 1: {}", sizes);
+        .sum()
+}
 
+fn part2(dtree: &DTree) -> usize {
     let total = 70000000;
     let required = 30000000;
     let currenet = dtree.size();
     let remaining = total - currenet;
     let need_to_free = required - remaining;
 
-    let smallest_freeable = dtree
+    dtree
         .dirs()
         .iter()
         .filter(|dir| dir.size() >= need_to_free)
         .map(|dir| dir.size())
         .min()
-        .unwrap();
-
-    println!("Part 2: {}", smallest_freeable);
+        .unwrap()
+}
 
-    Ok(())
+fn main() -> anyhow::Result<()> {
+    Day::new(7, INPUT, parse)
+        .part1("tree", part1)
+        .part2("tree", part2)
+        .main()
 }
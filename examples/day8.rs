@@ -1,20 +1,16 @@
-use itertools::Itertools;
+use aoc::{
+    parse::{grid, parse_all},
+    Day,
+};
 
 const INPUT: &str = include_str!("inputs/day8.txt");
 
 struct Grid(Vec<Vec<usize>>);
 
 impl Grid {
-    fn parse(input: &str) -> Grid {
-        let v = input
-            .lines()
-            .map(|line| {
-                line.chars()
-                    .filter_map(|c| c.to_digit(10).map(|x| x as usize))
-                    .collect_vec()
-            })
-            .collect::<Vec<Vec<usize>>>();
-        Self(v)
+    fn parse(input: &str) -> anyhow::Result<Grid> {
+        let trees = grid("a tree height", |c| c.to_digit(10).map(|x| x as usize));
+        Ok(Self(parse_all(trees, input)?))
     }
 
     fn width(&self) -> usize {
@@ -112,9 +108,8 @@ fn part2(grid: &Grid) -> usize {
 }
 
 fn main() -> anyhow::Result<()> {
-    let grid = Grid::parse(INPUT);
-
-    println!("Part 1: {}", part1(&grid));
-    println!("Part 2: {}", part2(&grid));
-    Ok(())
+    Day::new(8, INPUT, Grid::parse)
+        .part1("scan", part1)
+        .part2("scan", part2)
+        .main()
 }
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use aoc::{sparse::SparseGrid, Day};
 
 const INPUT: &str = include_str!("inputs/day9.txt");
 
@@ -39,12 +39,12 @@ fn mov(
     hy: &mut i32,
     tx: &mut i32,
     ty: &mut i32,
-    visited: &mut HashSet<(i32, i32)>,
+    visited: &mut SparseGrid,
 ) {
     for _ in 0..dist {
         mov_head(hx, hy, dir);
-        let ntail = update_tail(*hx, *hy, tx, ty);
-        visited.insert(ntail);
+        let (ntx, nty) = update_tail(*hx, *hy, tx, ty);
+        visited.insert((ntx as isize, nty as isize));
     }
 }
 
@@ -52,10 +52,10 @@ fn part1(cmds: &[(char, usize)]) -> usize {
     let (mut hx, mut hy) = (0, 0);
     let (mut tx, mut ty) = (0, 0);
 
-    let mut tail_visited = HashSet::new();
+    let mut tail_visited = SparseGrid::new();
 
     // Insert initial position as visited
-    tail_visited.insert((tx, ty));
+    tail_visited.insert((0, 0));
 
     for (cmd, dist) in cmds {
         match cmd {
@@ -110,26 +110,22 @@ fn part1(cmds: &[(char, usize)]) -> usize {
     tail_visited.len()
 }
 
-fn mov_many(
-    dir: (i32, i32),
-    dist: usize,
-    rope: &mut [(i32, i32)],
-    visited: &mut HashSet<(i32, i32)>,
-) {
+fn mov_many(dir: (i32, i32), dist: usize, rope: &mut [(i32, i32)], visited: &mut SparseGrid) {
     for _ in 0..dist {
         let (hx, hy) = &mut rope[0];
         mov_head(hx, hy, dir);
         update_tail_many(rope);
-        visited.insert(rope[rope.len() - 1]);
+        let (ntx, nty) = rope[rope.len() - 1];
+        visited.insert((ntx as isize, nty as isize));
     }
 }
 
 fn part2(cmds: &[(char, usize)]) -> usize {
     let mut rope = vec![(0, 0); 10];
-    let mut tail_visited = HashSet::new();
+    let mut tail_visited = SparseGrid::new();
 
     // Insert initial position as visited
-    tail_visited.insert(rope[9]);
+    tail_visited.insert((0, 0));
 
     for (cmd, dist) in cmds {
         match cmd {
@@ -143,8 +139,8 @@ fn part2(cmds: &[(char, usize)]) -> usize {
     tail_visited.len()
 }
 
-fn main() -> anyhow::Result<()> {
-    let cmds: Vec<(char, usize)> = INPUT
+fn parse(input: &str) -> anyhow::Result<Vec<(char, usize)>> {
+    Ok(input
         .lines()
         .map(|line| {
             let mut toks = line.split_ascii_whitespace();
@@ -152,9 +148,12 @@ fn main() -> anyhow::Result<()> {
             let dist = toks.next().unwrap().parse::<usize>().unwrap();
             (c, dist)
         })
-        .collect();
+        .collect())
+}
 
-    println!("Part 1: {}", part1(&cmds));
-    println!("Part 2: {}", part2(&cmds));
-    Ok(())
+fn main() -> anyhow::Result<()> {
+    Day::new(9, INPUT, parse)
+        .part1("rope", |cmds| part1(cmds))
+        .part2("rope", |cmds| part2(cmds))
+        .main()
 }
@@ -0,0 +1,169 @@
+//! Allocation accounting through a counting global allocator.
+//!
+//! The allocator is only installed when the `alloc-stats` feature is enabled.
+//! Without it the counters are never touched and [`stats`] stays at zero.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Whether allocations are being counted in this build.
+pub const ENABLED: bool = cfg!(feature = "alloc-stats");
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Wraps the system allocator and counts every allocation it serves.
+pub struct CountingAlloc;
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn record_alloc(size: usize) {
+    ALLOCS.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(size, Ordering::Relaxed);
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+}
+
+fn record_dealloc(size: usize) {
+    CURRENT.fetch_sub(size, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record_dealloc(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            // A reallocation is counted as a fresh allocation of the new size
+            record_dealloc(layout.size());
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// A snapshot of the allocation counters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    /// Number of allocations (including reallocations)
+    pub allocs: usize,
+    /// Total bytes requested
+    pub bytes: usize,
+    /// Bytes currently live
+    pub current: usize,
+    /// Highest number of live bytes since the last [`reset_peak`]
+    pub peak: usize,
+}
+
+impl AllocStats {
+    /// Returns the allocations made between `earlier` and `self`. The peak is
+    /// given relative to the memory that was already live at `earlier`.
+    pub fn since(&self, earlier: &AllocStats) -> AllocStats {
+        AllocStats {
+            allocs: self.allocs - earlier.allocs,
+            bytes: self.bytes - earlier.bytes,
+            current: self.current,
+            peak: self.peak.saturating_sub(earlier.current),
+        }
+    }
+}
+
+/// Returns the current allocation counters.
+pub fn stats() -> AllocStats {
+    AllocStats {
+        allocs: ALLOCS.load(Ordering::Relaxed),
+        bytes: BYTES.load(Ordering::Relaxed),
+        current: CURRENT.load(Ordering::Relaxed),
+        peak: PEAK.load(Ordering::Relaxed),
+    }
+}
+
+/// Restarts peak tracking from the memory that is live right now.
+pub fn reset_peak() {
+    PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// Formats a byte count with a binary unit, e.g. "1.5 MiB".
+pub fn fmt_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024. && unit < UNITS.len() - 1 {
+        value /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_bytes() {
+        assert_eq!(fmt_bytes(0), "0 B");
+        assert_eq!(fmt_bytes(1023), "1023 B");
+        assert_eq!(fmt_bytes(1536), "1.5 KiB");
+        assert_eq!(fmt_bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+
+    #[test]
+    fn since_is_relative_to_earlier() {
+        let earlier = AllocStats {
+            allocs: 10,
+            bytes: 1000,
+            current: 500,
+            peak: 800,
+        };
+        let later = AllocStats {
+            allocs: 15,
+            bytes: 1600,
+            current: 600,
+            peak: 900,
+        };
+        let diff = later.since(&earlier);
+        assert_eq!(diff.allocs, 5);
+        assert_eq!(diff.bytes, 600);
+        assert_eq!(diff.peak, 400);
+    }
+
+    #[cfg(feature = "alloc-stats")]
+    #[test]
+    fn counts_allocations() {
+        let before = stats();
+        let v = vec![0u8; 4096];
+        let after = stats();
+        drop(v);
+        assert!(after.allocs > before.allocs);
+        assert!(after.bytes >= before.bytes + 4096);
+    }
+}
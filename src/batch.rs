@@ -0,0 +1,227 @@
+//! Running a day against a directory of puzzle inputs.
+//!
+//! Everybody gets their own puzzle input, and an assumption that holds for
+//! one input, like the number of stacks in day 5, may not hold for another.
+//! With `--batch DIR` the runner parses every file in `DIR` and runs the
+//! selected implementations on it, with the files spread over all cores, and
+//! prints one row per file and part. A panic or error in one file is reported
+//! in its row and doesn't stop the others.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail};
+use rayon::prelude::*;
+
+use crate::{
+    cancel,
+    day::{Answer, Day, Solver},
+    runner::{select, solve, Options},
+};
+
+/// The outcome of one part on one input, or of parsing it if `part` is
+/// `None`.
+struct Row {
+    file: String,
+    part: Option<usize>,
+    implementation: &'static str,
+    answer: Answer,
+    elapsed: Duration,
+}
+
+impl Row {
+    fn failed(&self) -> bool {
+        self.answer
+            .as_ref()
+            .is_err_and(|e| !cancel::is_timed_out(e))
+    }
+
+    fn cells(&self) -> [String; 5] {
+        let answer = match &self.answer {
+            Ok(answer) if answer.contains('\n') => {
+                format!("picture of {} lines", answer.lines().count())
+            }
+            Ok(answer) => answer.clone(),
+            Err(e) if cancel::is_timed_out(e) => format!("{e:#}"),
+            Err(e) => format!("failed: {e:#}"),
+        };
+        [
+            self.file.clone(),
+            self.part.map_or("-".to_string(), |part| part.to_string()),
+            self.implementation.to_string(),
+            answer,
+            format!("{:.2?}", self.elapsed),
+        ]
+    }
+}
+
+/// Runs the parts of `day` that `opts` selects on every file in `dir` and
+/// prints a table of the results. Fails if any input failed.
+pub fn run<I>(day: &Day<I>, dir: &Path, opts: &Options) -> anyhow::Result<()> {
+    let mut files = fs_err::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<anyhow::Result<Vec<PathBuf>>>()?;
+    files.retain(|path| path.is_file());
+    files.sort();
+    if files.is_empty() {
+        bail!("no input files in {}", dir.display());
+    }
+
+    // Select before starting, so that an unknown --impl fails right away
+    let solvers = [select(day, 1, opts)?, select(day, 2, opts)?];
+    // Every file would save to the same checkpoint
+    let opts = Options {
+        checkpoint: None,
+        ..opts.clone()
+    };
+
+    let rows = files
+        .par_iter()
+        .flat_map_iter(|path| run_file(day, &solvers, path, &opts))
+        .collect::<Vec<_>>();
+
+    print_table(&rows);
+
+    let mut failed = rows
+        .iter()
+        .filter(|row| row.failed())
+        .map(|row| row.file.as_str())
+        .collect::<Vec<_>>();
+    failed.dedup();
+    if !failed.is_empty() {
+        bail!(
+            "day {} failed on {} of {} inputs: {}",
+            day.number(),
+            failed.len(),
+            files.len(),
+            failed.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn run_file<I>(
+    day: &Day<I>,
+    solvers: &[Vec<&Solver<I>>; 2],
+    path: &Path,
+    opts: &Options,
+) -> Vec<Row> {
+    let file = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into(),
+    );
+
+    let started = Instant::now();
+    let input = catch_panic(|| {
+        let text = fs_err::read_to_string(path)?;
+        day.parse(&text)
+    });
+    let input = match input {
+        Ok(input) => input,
+        Err(e) => {
+            return vec![Row {
+                file,
+                part: None,
+                implementation: "parse",
+                answer: Err(e),
+                elapsed: started.elapsed(),
+            }]
+        }
+    };
+
+    let mut rows = vec![];
+    for (part, solvers) in (1..=2).zip(solvers) {
+        for solver in solvers {
+            let started = Instant::now();
+            let answer = catch_panic(|| solve(day, part, solver, &input, opts));
+            rows.push(Row {
+                file: file.clone(),
+                part: Some(part),
+                implementation: solver.name(),
+                answer,
+                elapsed: started.elapsed(),
+            });
+        }
+    }
+    rows
+}
+
+/// Runs `f`, turning a panic into an error.
+fn catch_panic<T>(f: impl FnOnce() -> anyhow::Result<T>) -> anyhow::Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(|msg| msg.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown cause".to_string());
+        Err(anyhow!("panicked: {msg}"))
+    })
+}
+
+fn print_table(rows: &[Row]) {
+    let header = ["input", "part", "implementation", "answer", "time"].map(String::from);
+    let cells = rows.iter().map(Row::cells).collect::<Vec<_>>();
+    let mut widths = header.clone().map(|cell| cell.chars().count());
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    for row in std::iter::once(&header).chain(&cells) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_each_input() {
+        let dir = std::env::temp_dir().join(format!("aoc-batch-{}", std::process::id()));
+        fs_err::create_dir_all(&dir).unwrap();
+        fs_err::write(dir.join("a.txt"), "3").unwrap();
+        fs_err::write(dir.join("b.txt"), "x").unwrap();
+        fs_err::write(dir.join("c.txt"), "0").unwrap();
+
+        let day = Day::new(0, "", |input| Ok(input.trim().parse::<u32>()?))
+            .part1("double", |x| x * 2)
+            .part2("invert", |x| 12 / x);
+        let solvers = [select(&day, 1, &Options::default()).unwrap(), vec![]];
+        let rows = |file: &str| run_file(&day, &solvers, &dir.join(file), &Options::default());
+
+        let a = rows("a.txt");
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].answer.as_ref().unwrap(), "6");
+        let b = rows("b.txt");
+        assert_eq!((b[0].part, b[0].implementation), (None, "parse"));
+        assert!(b[0].failed());
+
+        let solvers = [vec![], select(&day, 2, &Options::default()).unwrap()];
+        let c = run_file(&day, &solvers, &dir.join("c.txt"), &Options::default());
+        let err = c[0].answer.as_ref().unwrap_err().to_string();
+        assert!(
+            err.starts_with("panicked: attempt to divide by zero"),
+            "{err}"
+        );
+
+        let opts = Options {
+            batch: Some(dir.clone()),
+            ..Options::default()
+        };
+        assert!(run(&day, &dir, &opts).is_err());
+        fs_err::remove_file(dir.join("b.txt")).unwrap();
+        fs_err::remove_file(dir.join("c.txt")).unwrap();
+        assert!(run(&day, &dir, &opts).is_ok());
+        fs_err::remove_dir_all(&dir).unwrap();
+    }
+}
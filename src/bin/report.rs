@@ -0,0 +1,141 @@
+//! Runs every day and writes a status page of their parts.
+//!
+//! ```text
+//! cargo run --release --bin report -- [OPTIONS] [DAY...]
+//!
+//!     --html          write HTML instead of Markdown
+//!     --mask          hide the answers
+//!     --all           run every implementation of each part, not just the default
+//!     --budget SECS   time budget of each implementation, 30 seconds by default
+//!     --out PATH      write the report to PATH instead of stdout
+//! ```
+
+use std::{path::PathBuf, process::Command};
+
+use anyhow::{bail, Context};
+use aoc::report::{PartReport, Report, Status};
+
+#[derive(Debug)]
+struct Options {
+    html: bool,
+    mask: bool,
+    all: bool,
+    budget: String,
+    out: Option<PathBuf>,
+    days: Vec<u32>,
+}
+
+impl Options {
+    fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Options> {
+        let mut opts = Options {
+            html: false,
+            mask: false,
+            all: false,
+            budget: "30".to_string(),
+            out: None,
+            days: vec![],
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .with_context(|| format!("{arg} expects a value"))
+            };
+            match arg.as_str() {
+                "--html" => opts.html = true,
+                "--mask" => opts.mask = true,
+                "--all" => opts.all = true,
+                "--budget" => opts.budget = value()?,
+                "--out" => opts.out = Some(value()?.into()),
+                day => match day.parse() {
+                    Ok(day) => opts.days.push(day),
+                    Err(_) => bail!("unknown argument: {arg}"),
+                },
+            }
+        }
+        Ok(opts)
+    }
+}
+
+/// Returns the numbers of the days that have a solution in `examples/`.
+fn find_days() -> anyhow::Result<Vec<u32>> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut days = vec![];
+    for entry in fs_err::read_dir(dir)? {
+        let name = entry?.file_name();
+        let day: Option<u32> = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("day")?.strip_suffix(".rs")?.parse().ok());
+        days.extend(day);
+    }
+    days.sort();
+    Ok(days)
+}
+
+/// Runs `day` with `--summary` and collects its reports. Parts the day didn't
+/// report because it crashed are reported as failed.
+fn run_day(day: u32, opts: &Options) -> anyhow::Result<Vec<PartReport>> {
+    eprintln!("Running day {day}");
+    let mut cmd = Command::new(env!("CARGO"));
+    cmd.args(["run", "--quiet", "--release", "--example"])
+        .arg(format!("day{day}"))
+        .args(["--", "--summary", "--budget", &opts.budget]);
+    if opts.all {
+        cmd.arg("--all");
+    }
+    let output = cmd.output()?;
+
+    let mut reports = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(PartReport::from_line)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    for part in 1..=2 {
+        if !reports.iter().any(|r| r.part == part) {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr
+                .lines()
+                .find_map(|line| match line.find("panicked") {
+                    Some(at) => Some(&line[at..]),
+                    None => line.starts_with("error").then_some(line),
+                })
+                .unwrap_or("no output");
+            reports.push(PartReport {
+                day,
+                part,
+                implementation: None,
+                status: Status::Failed(format!("crashed ({}): {reason}", output.status)),
+                elapsed: None,
+            });
+        }
+    }
+    reports.sort_by_key(|r| r.part);
+    Ok(reports)
+}
+
+fn main() -> anyhow::Result<()> {
+    let opts = Options::parse(std::env::args().skip(1))?;
+    let days = if opts.days.is_empty() {
+        find_days()?
+    } else {
+        opts.days.clone()
+    };
+
+    let mut report = Report {
+        mask: opts.mask,
+        ..Report::default()
+    };
+    for day in days {
+        report.parts.extend(run_day(day, &opts)?);
+    }
+
+    let page = if opts.html {
+        report.to_html()
+    } else {
+        report.to_markdown()
+    };
+    match &opts.out {
+        Some(path) => fs_err::write(path, page)?,
+        None => print!("{page}"),
+    }
+    Ok(())
+}
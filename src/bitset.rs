@@ -0,0 +1,351 @@
+//! Sets of small integers stored as bits.
+//!
+//! [`Bits`] holds the numbers below `64 * W` in `W` words on the stack, so
+//! it is `Copy` and cheap to use as part of a memo key. [`BitSet`] grows to
+//! fit the largest number it holds, for universes like the cells of a map.
+//! Both support the usual set operations, with the `|`, `&` and `-`
+//! operators for union, intersection and difference, and iterate over their
+//! members in increasing order.
+
+use std::{
+    fmt,
+    ops::{BitAnd, BitOr, Sub},
+};
+
+const WORD: usize = u64::BITS as usize;
+
+/// Iterates over the set bits of `words` in increasing order.
+fn members(words: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    words.iter().enumerate().flat_map(|(i, &word)| {
+        let mut bits = word;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let bit = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            Some(i * WORD + bit)
+        })
+    })
+}
+
+/// A set of the numbers below `64 * W`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bits<const W: usize>([u64; W]);
+
+/// A set of the numbers below 64.
+pub type Bits64 = Bits<1>;
+
+impl<const W: usize> Default for Bits<W> {
+    fn default() -> Self {
+        Bits([0; W])
+    }
+}
+
+impl<const W: usize> Bits<W> {
+    /// The number of values the set can hold.
+    pub const CAPACITY: usize = W * WORD;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, n: usize) -> bool {
+        n < Self::CAPACITY && self.0[n / WORD] >> (n % WORD) & 1 == 1
+    }
+
+    /// Adds `n`, which must be below [`Self::CAPACITY`]. Returns whether it
+    /// wasn't in the set yet.
+    pub fn insert(&mut self, n: usize) -> bool {
+        assert!(n < Self::CAPACITY, "{n} doesn't fit in {W} words");
+        let had = self.contains(n);
+        self.0[n / WORD] |= 1 << (n % WORD);
+        !had
+    }
+
+    /// Removes `n`. Returns whether it was in the set.
+    pub fn remove(&mut self, n: usize) -> bool {
+        let had = self.contains(n);
+        if had {
+            self.0[n / WORD] &= !(1 << (n % WORD));
+        }
+        had
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.0.iter().zip(&other.0).all(|(a, b)| a & !b == 0)
+    }
+
+    /// Returns the smallest member.
+    pub fn first(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        members(&self.0)
+    }
+
+    fn zip_with(mut self, other: Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a = f(*a, b);
+        }
+        self
+    }
+}
+
+impl<const W: usize> BitOr for Bits<W> {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        self.zip_with(other, |a, b| a | b)
+    }
+}
+
+impl<const W: usize> BitAnd for Bits<W> {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        self.zip_with(other, |a, b| a & b)
+    }
+}
+
+impl<const W: usize> Sub for Bits<W> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.zip_with(other, |a, b| a & !b)
+    }
+}
+
+impl<const W: usize> FromIterator<usize> for Bits<W> {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<const W: usize> Extend<usize> for Bits<W> {
+    fn extend<T: IntoIterator<Item = usize>>(&mut self, iter: T) {
+        for n in iter {
+            self.insert(n);
+        }
+    }
+}
+
+impl<const W: usize> fmt::Debug for Bits<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// A set of numbers that grows to fit its largest member.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    /// Never ends in a zero word, so that equal sets are equal words
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> BitSet {
+        BitSet::default()
+    }
+
+    /// Creates an empty set with room for the numbers below `n`.
+    pub fn with_capacity(n: usize) -> BitSet {
+        BitSet {
+            words: Vec::with_capacity(n.div_ceil(WORD)),
+        }
+    }
+
+    fn trim(&mut self) {
+        while self.words.last() == Some(&0) {
+            self.words.pop();
+        }
+    }
+
+    pub fn contains(&self, n: usize) -> bool {
+        self.words
+            .get(n / WORD)
+            .is_some_and(|word| word >> (n % WORD) & 1 == 1)
+    }
+
+    /// Adds `n`. Returns whether it wasn't in the set yet.
+    pub fn insert(&mut self, n: usize) -> bool {
+        if n / WORD >= self.words.len() {
+            self.words.resize(n / WORD + 1, 0);
+        }
+        let had = self.contains(n);
+        self.words[n / WORD] |= 1 << (n % WORD);
+        !had
+    }
+
+    /// Removes `n`. Returns whether it was in the set.
+    pub fn remove(&mut self, n: usize) -> bool {
+        let had = self.contains(n);
+        if had {
+            self.words[n / WORD] &= !(1 << (n % WORD));
+            self.trim();
+        }
+        had
+    }
+
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn is_subset(&self, other: &BitSet) -> bool {
+        self.words.len() <= other.words.len()
+            && self
+                .words
+                .iter()
+                .zip(&other.words)
+                .all(|(a, b)| a & !b == 0)
+    }
+
+    /// Returns the smallest member.
+    pub fn first(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        members(&self.words)
+    }
+
+    /// Adds the members of `other`.
+    pub fn union_with(&mut self, other: &BitSet) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a |= b;
+        }
+    }
+
+    /// Keeps only the members that are also in `other`.
+    pub fn intersect_with(&mut self, other: &BitSet) {
+        self.words.truncate(other.words.len());
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a &= b;
+        }
+        self.trim();
+    }
+
+    /// Removes the members of `other`.
+    pub fn difference_with(&mut self, other: &BitSet) {
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a &= !b;
+        }
+        self.trim();
+    }
+}
+
+impl BitOr for &BitSet {
+    type Output = BitSet;
+
+    fn bitor(self, other: &BitSet) -> BitSet {
+        let mut set = self.clone();
+        set.union_with(other);
+        set
+    }
+}
+
+impl BitAnd for &BitSet {
+    type Output = BitSet;
+
+    fn bitand(self, other: &BitSet) -> BitSet {
+        let mut set = self.clone();
+        set.intersect_with(other);
+        set
+    }
+}
+
+impl Sub for &BitSet {
+    type Output = BitSet;
+
+    fn sub(self, other: &BitSet) -> BitSet {
+        let mut set = self.clone();
+        set.difference_with(other);
+        set
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut set = BitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<T: IntoIterator<Item = usize>>(&mut self, iter: T) {
+        for n in iter {
+            self.insert(n);
+        }
+    }
+}
+
+impl fmt::Debug for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_width_sets() {
+        let a: Bits<2> = [1, 5, 64, 127].into_iter().collect();
+        let b: Bits<2> = [5, 64, 100].into_iter().collect();
+        assert_eq!((a & b).iter().collect::<Vec<_>>(), [5, 64]);
+        assert_eq!((a | b).len(), 5);
+        assert_eq!((a - b).iter().collect::<Vec<_>>(), [1, 127]);
+        assert!((a & b).is_subset(&a) && !a.is_subset(&b));
+        assert_eq!(a.first(), Some(1));
+        assert_eq!(format!("{:?}", a - b), "{1, 127}");
+
+        let mut c = Bits64::new();
+        assert!(c.insert(63) && !c.insert(63));
+        assert!(!c.contains(64));
+        assert!(c.remove(63) && c.is_empty());
+    }
+
+    #[test]
+    fn growable_sets() {
+        let mut a: BitSet = [3, 200].into_iter().collect();
+        let b: BitSet = [3, 7].into_iter().collect();
+        assert_eq!((&a & &b), [3].into_iter().collect());
+        assert_eq!((&a | &b).iter().collect::<Vec<_>>(), [3, 7, 200]);
+        assert_eq!((&a - &b).iter().collect::<Vec<_>>(), [200]);
+
+        // Removing the largest member shrinks the set back to equal a set
+        // that never had it
+        assert!(a.remove(200));
+        assert_eq!(a, [3].into_iter().collect());
+        assert!(a.is_subset(&b) && !b.is_subset(&a));
+        a.clear();
+        assert!(a.is_empty() && a.first().is_none());
+    }
+}
@@ -0,0 +1,205 @@
+//! Cooperative cancellation for long running solvers.
+//!
+//! The runner installs a [`Token`] for each solver it runs. A slow solver
+//! polls [`is_cancelled`] in its hot loop, or calls [`check`] at a point where
+//! it can describe how far it got, and returns the [`TimedOut`] error from
+//! there. Without an installed token neither ever cancels. After
+//! [`cancel_on_interrupt`], Ctrl-C cancels every token.
+
+use std::{
+    cell::RefCell,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+
+/// Set by the Ctrl-C handler of [`cancel_on_interrupt`]
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Makes Ctrl-C cancel every token instead of killing the process, so that
+/// the solvers can report how far they got and save their checkpoints. A
+/// second Ctrl-C kills the process for solvers that never poll.
+pub fn cancel_on_interrupt() -> anyhow::Result<()> {
+    let handler = || {
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        }
+    };
+    match ctrlc::set_handler(handler) {
+        Ok(()) | Err(ctrlc::Error::MultipleHandlers) => Ok(()),
+        Err(e) => Err(e).context("cannot handle Ctrl-C"),
+    }
+}
+
+/// Cancels a run by hand or when its time budget runs out. Clones share the
+/// same state, so a token can be cancelled from another thread.
+#[derive(Clone, Debug)]
+pub struct Token {
+    cancelled: Arc<AtomicBool>,
+    started: Instant,
+    deadline: Option<Instant>,
+}
+
+impl Token {
+    /// A token without a time budget.
+    pub fn new() -> Token {
+        Token {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            started: Instant::now(),
+            deadline: None,
+        }
+    }
+
+    /// A token that cancels once `budget` has passed.
+    pub fn with_budget(budget: Duration) -> Token {
+        let token = Token::new();
+        Token {
+            deadline: Some(token.started + budget),
+            ..token
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            self.cancel();
+            return true;
+        }
+        match self.deadline {
+            // Latch the timeout so later polls don't read the clock
+            Some(deadline) if Instant::now() >= deadline => {
+                self.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns [`TimedOut`] with the progress described by `progress` if the
+    /// token is cancelled.
+    pub fn check<D: fmt::Display>(&self, progress: impl FnOnce() -> D) -> Result<(), TimedOut> {
+        if self.is_cancelled() {
+            return Err(TimedOut {
+                elapsed: self.started.elapsed(),
+                interrupted: INTERRUPTED.load(Ordering::Relaxed),
+                progress: progress().to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for Token {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The error of a solver that was cancelled before it found its answer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimedOut {
+    pub elapsed: Duration,
+    /// Whether it was cancelled by Ctrl-C rather than its time budget
+    pub interrupted: bool,
+    /// How far the solver got, e.g. "1200 of 4000 rows scanned"
+    pub progress: String,
+}
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cause = match self.interrupted {
+            true => "interrupted",
+            false => "timed out",
+        };
+        write!(f, "{cause} after {:.2?}", self.elapsed)?;
+        if !self.progress.is_empty() {
+            write!(f, " ({})", self.progress)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Returns whether `err` is, or was caused by, a [`TimedOut`].
+pub fn is_timed_out(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| cause.is::<TimedOut>())
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Token>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `token` installed as the current token of this thread.
+pub fn scoped<T>(token: &Token, f: impl FnOnce() -> T) -> T {
+    let outer = CURRENT.with(|current| current.replace(Some(token.clone())));
+    let ret = f();
+    CURRENT.with(|current| *current.borrow_mut() = outer);
+    ret
+}
+
+/// Returns the token installed on this thread, if any.
+pub fn current() -> Option<Token> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Returns whether the current token is cancelled.
+pub fn is_cancelled() -> bool {
+    CURRENT.with(|current| current.borrow().as_ref().is_some_and(Token::is_cancelled))
+}
+
+/// Checks the current token, see [`Token::check`].
+pub fn check<D: fmt::Display>(progress: impl FnOnce() -> D) -> Result<(), TimedOut> {
+    CURRENT.with(|current| match current.borrow().as_ref() {
+        Some(token) => token.check(progress),
+        None => Ok(()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_out_after_budget() {
+        let token = Token::with_budget(Duration::from_millis(20));
+        assert!(token.check(|| "").is_ok());
+        std::thread::sleep(Duration::from_millis(30));
+
+        let err = token.check(|| "3 of 4 rows").unwrap_err();
+        assert_eq!(err.progress, "3 of 4 rows");
+        assert!(err.to_string().starts_with("timed out after"));
+        assert!(err.to_string().ends_with("(3 of 4 rows)"));
+        let interrupted = TimedOut {
+            interrupted: true,
+            ..err.clone()
+        };
+        assert!(interrupted.to_string().starts_with("interrupted after"));
+        assert!(is_timed_out(&anyhow::Error::new(err).context("part 2")));
+    }
+
+    #[test]
+    fn scoped_token_is_current() {
+        assert!(!is_cancelled());
+        let token = Token::new();
+        scoped(&token, || {
+            assert!(check(|| "").is_ok());
+            current().unwrap().cancel();
+            assert!(is_cancelled());
+            assert!(check(|| "").is_err());
+        });
+        assert!(current().is_none());
+        assert!(token.is_cancelled());
+    }
+}
@@ -0,0 +1,191 @@
+//! Checkpoints for long running solvers.
+//!
+//! When the runner is given a checkpoint directory it installs a
+//! [`Checkpointer`] for each solver it runs. A solver that can resume loads
+//! its state with [`Checkpointer::load`] when it starts, saves a [`Record`] of
+//! it whenever [`Checkpointer::due`], and calls [`Checkpointer::finish`] once
+//! it has its answer. A checkpoint is due at every interval and as soon as
+//! the run is cancelled, so a timed out or interrupted solver leaves its
+//! state behind.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+
+use crate::cancel;
+
+/// The state of a solver as `key value` lines.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Record(BTreeMap<String, String>);
+
+impl Record {
+    pub fn new() -> Record {
+        Record::default()
+    }
+
+    /// Sets `key` to `value`, which must fit on one line.
+    pub fn set(&mut self, key: &str, value: impl fmt::Display) {
+        let value = value.to_string();
+        assert!(
+            !key.contains(char::is_whitespace) && !value.contains('\n'),
+            "cannot record {key:?} = {value:?}"
+        );
+        self.0.insert(key.to_string(), value);
+    }
+
+    pub fn with(mut self, key: &str, value: impl fmt::Display) -> Record {
+        self.set(key, value);
+        self
+    }
+
+    pub fn get<T>(&self, key: &str) -> anyhow::Result<T>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        let value = self
+            .0
+            .get(key)
+            .with_context(|| format!("checkpoint has no {key}"))?;
+        value
+            .parse()
+            .with_context(|| format!("invalid {key} in checkpoint: {value}"))
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.0 {
+            writeln!(f, "{key} {value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Record {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut record = Record::new();
+        for line in s.lines() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            record.0.insert(key.to_string(), value.to_string());
+        }
+        Ok(record)
+    }
+}
+
+/// Saves and loads the checkpoint of one solver.
+#[derive(Clone, Debug)]
+pub struct Checkpointer {
+    path: PathBuf,
+    interval: Duration,
+    last: Cell<Instant>,
+}
+
+impl Checkpointer {
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Checkpointer {
+        Checkpointer {
+            path: path.into(),
+            interval,
+            last: Cell::new(Instant::now()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the saved state, if there is any.
+    pub fn load(&self) -> anyhow::Result<Option<Record>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let text = fs_err::read_to_string(&self.path)?;
+        let record = text.parse()?;
+        crate::phase::note(
+            "checkpoint",
+            format!("resuming from {}", self.path.display()),
+        );
+        Ok(Some(record))
+    }
+
+    /// Returns whether the state should be saved now.
+    pub fn due(&self) -> bool {
+        self.last.get().elapsed() >= self.interval || cancel::is_cancelled()
+    }
+
+    /// Replaces the saved state with `record`.
+    pub fn save(&self, record: &Record) -> anyhow::Result<()> {
+        // Write next to the checkpoint first so an interrupted save doesn't
+        // leave a truncated file behind
+        let tmp = self.path.with_extension("tmp");
+        fs_err::write(&tmp, record.to_string())?;
+        fs_err::rename(&tmp, &self.path)?;
+        self.last.set(Instant::now());
+        Ok(())
+    }
+
+    /// Removes the saved state of a solver that is done.
+    pub fn finish(&self) -> anyhow::Result<()> {
+        if self.path.exists() {
+            fs_err::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Checkpointer>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `checkpointer` installed as the checkpointer of this
+/// thread.
+pub fn scoped<T>(checkpointer: Option<&Checkpointer>, f: impl FnOnce() -> T) -> T {
+    let outer = CURRENT.with(|current| current.replace(checkpointer.cloned()));
+    let ret = f();
+    CURRENT.with(|current| *current.borrow_mut() = outer);
+    ret
+}
+
+/// Returns the checkpointer installed on this thread, if any.
+pub fn current() -> Option<Checkpointer> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_roundtrip() {
+        let record = Record::new().with("round", 12).with("map", "0f3c");
+        let parsed: Record = record.to_string().parse().unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.get::<usize>("round").unwrap(), 12);
+        assert!(parsed.get::<usize>("map").is_err());
+        assert!(parsed.get::<usize>("floor").is_err());
+    }
+
+    #[test]
+    fn saves_loads_and_finishes() {
+        let path = std::env::temp_dir().join(format!("aoc-checkpoint-{}.txt", std::process::id()));
+        let checkpointer = Checkpointer::new(&path, Duration::from_secs(3600));
+        assert!(checkpointer.load().unwrap().is_none());
+        assert!(!checkpointer.due());
+
+        let record = Record::new().with("round", 3);
+        checkpointer.save(&record).unwrap();
+        assert_eq!(checkpointer.load().unwrap(), Some(record));
+
+        checkpointer.finish().unwrap();
+        assert!(!path.exists());
+    }
+}
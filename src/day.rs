@@ -0,0 +1,394 @@
+//! Registration of a day's parser and the implementations of its parts.
+//!
+//! Every part can have several named implementations. The first one
+//! registered is the default; the others are alternatives that the runner can
+//! select, benchmark or cross-check against it. An alternative too slow to
+//! run with the others is marked with [`Day::on_request`], and only runs when
+//! selected by name.
+//!
+//! A day also declares the properties of the input its implementations rely
+//! on, like the number of monkeys in day 11, with [`Day::assume`]. They are
+//! checked right after parsing, so an input that breaks one is rejected with
+//! a message that says which, rather than causing an index panic or a wrong
+//! answer later.
+//!
+//! Besides its parts, a day can offer modes with [`Day::mode`], like the
+//! inventory stats of day 1. A mode reports on the parsed input instead of
+//! solving the parts, and is chosen on the command line by its name.
+
+use std::fmt;
+
+use anyhow::Context;
+
+/// The answer to a part, rendered for printing and comparison.
+pub type Answer = anyhow::Result<String>;
+
+/// Conversion of a part's return value into an [`Answer`].
+pub trait IntoAnswer {
+    fn into_answer(self) -> Answer;
+}
+
+macro_rules! impl_into_answer {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoAnswer for $ty {
+                fn into_answer(self) -> Answer {
+                    Ok(self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_into_answer!(u32, u64, u128, usize, i32, i64, i128, isize, String, &str);
+
+impl<T: IntoAnswer, E: Into<anyhow::Error>> IntoAnswer for Result<T, E> {
+    fn into_answer(self) -> Answer {
+        self.map_err(Into::into)?.into_answer()
+    }
+}
+
+type Parser<I> = Box<dyn Fn(&str) -> anyhow::Result<I> + Send + Sync>;
+type Check<I> = Box<dyn Fn(&I) -> anyhow::Result<()> + Send + Sync>;
+type Report<I> = Box<dyn Fn(&I, Option<&str>) -> anyhow::Result<String> + Send + Sync>;
+
+/// A named implementation of a part.
+pub struct Solver<I> {
+    name: &'static str,
+    on_request: bool,
+    solve: Box<dyn Fn(&I) -> Answer + Send + Sync>,
+}
+
+impl<I> Solver<I> {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Whether the implementation only runs when selected by name
+    pub fn on_request(&self) -> bool {
+        self.on_request
+    }
+
+    pub fn solve(&self, input: &I) -> Answer {
+        (self.solve)(input)
+    }
+}
+
+impl<I> fmt::Debug for Solver<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Solver").field("name", &self.name).finish()
+    }
+}
+
+/// A way to run a day that reports on the parsed input instead of solving
+/// the parts.
+pub struct Mode<I> {
+    name: &'static str,
+    value: Option<&'static str>,
+    description: &'static str,
+    report: Report<I>,
+}
+
+impl<I> Mode<I> {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The name of the value the mode takes, if it takes one
+    pub fn value(&self) -> Option<&'static str> {
+        self.value
+    }
+
+    /// Returns the report on `input`, given the value if the mode takes one.
+    pub fn report(&self, input: &I, value: Option<&str>) -> anyhow::Result<String> {
+        (self.report)(input, value)
+    }
+}
+
+impl<I> fmt::Display for Mode<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "--{}", self.name)?;
+        if let Some(value) = self.value {
+            write!(f, " {value}")?;
+        }
+        write!(f, ": {}", self.description)
+    }
+}
+
+/// A property of the input that the implementations rely on.
+struct Assumption<I> {
+    description: &'static str,
+    check: Check<I>,
+}
+
+/// A day's puzzle: its embedded input, its parser, the assumptions about the
+/// input and the implementations of both parts.
+pub struct Day<I> {
+    number: u32,
+    input: &'static str,
+    parse: Parser<I>,
+    assumptions: Vec<Assumption<I>>,
+    parts: [Vec<Solver<I>>; 2],
+    /// The part whose implementation was registered last
+    last_part: Option<usize>,
+    modes: Vec<Mode<I>>,
+}
+
+impl<I: 'static> Day<I> {
+    pub fn new(
+        number: u32,
+        input: &'static str,
+        parse: impl Fn(&str) -> anyhow::Result<I> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            number,
+            input,
+            parse: Box::new(parse),
+            assumptions: vec![],
+            parts: [vec![], vec![]],
+            last_part: None,
+            modes: vec![],
+        }
+    }
+
+    /// Declares that the implementations assume `description` holds for the
+    /// parsed input. `check` returns an error that says how the input breaks
+    /// it, if it does.
+    pub fn assume(
+        mut self,
+        description: &'static str,
+        check: impl Fn(&I) -> anyhow::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.assumptions.push(Assumption {
+            description,
+            check: Box::new(check),
+        });
+        self
+    }
+
+    /// Registers an implementation of part 1.
+    pub fn part1<A: IntoAnswer>(
+        self,
+        name: &'static str,
+        f: impl Fn(&I) -> A + Send + Sync + 'static,
+    ) -> Self {
+        self.register(1, name, f)
+    }
+
+    /// Registers an implementation of part 2.
+    pub fn part2<A: IntoAnswer>(
+        self,
+        name: &'static str,
+        f: impl Fn(&I) -> A + Send + Sync + 'static,
+    ) -> Self {
+        self.register(2, name, f)
+    }
+
+    fn register<A: IntoAnswer>(
+        mut self,
+        part: usize,
+        name: &'static str,
+        f: impl Fn(&I) -> A + Send + Sync + 'static,
+    ) -> Self {
+        let solvers = &mut self.parts[part - 1];
+        assert!(
+            solvers.iter().all(|s| s.name != name),
+            "day {} part {part} already has an implementation named \"{name}\"",
+            self.number
+        );
+        solvers.push(Solver {
+            name,
+            on_request: false,
+            solve: Box::new(move |input| f(input).into_answer()),
+        });
+        self.last_part = Some(part);
+        self
+    }
+
+    /// Marks the implementation registered last as one that only runs when
+    /// selected by name, not with the others, because it takes too long. The
+    /// default implementation can't be one.
+    pub fn on_request(mut self) -> Self {
+        let part = self.last_part.expect("no implementation to mark");
+        let solvers = &mut self.parts[part - 1];
+        assert!(
+            solvers.len() > 1,
+            "day {} part {part} can't have its default implementation on request",
+            self.number
+        );
+        solvers.last_mut().unwrap().on_request = true;
+        self
+    }
+
+    /// Registers a mode that runs instead of the parts on `--NAME`, or on
+    /// `--NAME VALUE` if it takes a `value`. `report` is called with the
+    /// parsed input and the value, and returns what to print.
+    pub fn mode(
+        mut self,
+        name: &'static str,
+        value: Option<&'static str>,
+        description: &'static str,
+        report: impl Fn(&I, Option<&str>) -> anyhow::Result<String> + Send + Sync + 'static,
+    ) -> Self {
+        assert!(
+            self.find_mode(name).is_none(),
+            "day {} already has a mode named \"{name}\"",
+            self.number
+        );
+        self.modes.push(Mode {
+            name,
+            value,
+            description,
+            report: Box::new(report),
+        });
+        self
+    }
+
+    /// Runs the day from the command line, see [`crate::runner`].
+    pub fn main(self) -> anyhow::Result<()> {
+        crate::runner::main(&self)
+    }
+}
+
+impl<I> Day<I> {
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// The puzzle input embedded in the binary
+    pub fn input(&self) -> &'static str {
+        self.input
+    }
+
+    /// Parses `input` and checks the assumptions about it.
+    pub fn parse(&self, input: &str) -> anyhow::Result<I> {
+        let input = (self.parse)(input)?;
+        for assumption in &self.assumptions {
+            (assumption.check)(&input).with_context(|| {
+                format!(
+                    "the input breaks the assumption that {}",
+                    assumption.description
+                )
+            })?;
+        }
+        Ok(input)
+    }
+
+    /// Returns the descriptions of the assumptions about the input.
+    pub fn assumptions(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.assumptions.iter().map(|a| a.description)
+    }
+
+    /// Returns the implementations of `part` (1 or 2), default first.
+    pub fn solvers(&self, part: usize) -> &[Solver<I>] {
+        &self.parts[part - 1]
+    }
+
+    /// Returns the implementation of `part` called `name`.
+    pub fn solver(&self, part: usize, name: &str) -> Option<&Solver<I>> {
+        self.solvers(part).iter().find(|s| s.name == name)
+    }
+
+    /// Returns the modes in the order they were registered.
+    pub fn modes(&self) -> &[Mode<I>] {
+        &self.modes
+    }
+
+    /// Returns the mode called `name`.
+    pub fn find_mode(&self, name: &str) -> Option<&Mode<I>> {
+        self.modes.iter().find(|m| m.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+
+    fn day() -> Day<Vec<u32>> {
+        Day::new(0, "1\n2\n3", |input| {
+            Ok(input
+                .lines()
+                .map(|line| line.parse())
+                .collect::<Result<Vec<u32>, _>>()?)
+        })
+        .part1("sum", |v| v.iter().sum::<u32>())
+        .part1("reduce", |v| {
+            v.iter().copied().reduce(|a, b| a + b).unwrap_or(0)
+        })
+        .part2("max", |v| {
+            v.iter().max().copied().ok_or(anyhow::anyhow!("empty"))
+        })
+    }
+
+    #[test]
+    fn registers_solvers_in_order() {
+        let day = day();
+        let names = day.solvers(1).iter().map(|s| s.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["sum", "reduce"]);
+        assert!(day.solver(2, "max").is_some());
+        assert!(day.solver(2, "sum").is_none());
+    }
+
+    #[test]
+    fn solvers_return_answers() {
+        let day = day();
+        let input = day.parse(day.input()).unwrap();
+        assert_eq!(day.solver(1, "reduce").unwrap().solve(&input).unwrap(), "6");
+        assert_eq!(day.solvers(2)[0].solve(&input).unwrap(), "3");
+        assert!(day.solvers(2)[0].solve(&vec![]).is_err());
+    }
+
+    #[test]
+    fn checks_assumptions_after_parsing() {
+        let day = day().assume("there are at most 3 numbers", |v| {
+            anyhow::ensure!(v.len() <= 3, "found {} numbers", v.len());
+            Ok(())
+        });
+        assert!(day.parse("1\n2").is_ok());
+        let err = day.parse("1\n2\n3\n4").unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "the input breaks the assumption that there are at most 3 numbers: found 4 numbers"
+        );
+        assert_eq!(
+            day.assumptions().collect::<Vec<_>>(),
+            ["there are at most 3 numbers"]
+        );
+    }
+
+    #[test]
+    fn modes_report_on_the_input() {
+        let day = day().mode("scale", Some("FACTOR"), "scale the numbers", |v, factor| {
+            let factor = factor.unwrap().parse::<u32>()?;
+            Ok(v.iter().map(|n| n * factor).join(" "))
+        });
+        let input = day.parse(day.input()).unwrap();
+        let mode = day.find_mode("scale").unwrap();
+        assert_eq!(mode.report(&input, Some("2")).unwrap(), "2 4 6");
+        assert!(mode.report(&input, Some("x")).is_err());
+        assert_eq!(mode.to_string(), "--scale FACTOR: scale the numbers");
+        assert!(day.find_mode("sum").is_none());
+    }
+
+    #[test]
+    fn marks_implementations_on_request() {
+        let day = day().part1("len", |v| v.len()).on_request();
+        assert!(!day.solver(1, "reduce").unwrap().on_request());
+        assert!(day.solver(1, "len").unwrap().on_request());
+    }
+
+    #[test]
+    #[should_panic(expected = "default implementation on request")]
+    fn default_is_never_on_request() {
+        // The default of part 2 was registered last
+        day().on_request();
+    }
+
+    #[test]
+    #[should_panic(expected = "already has an implementation")]
+    fn rejects_duplicate_names() {
+        day().part1("sum", |v| v.len());
+    }
+}
@@ -0,0 +1,313 @@
+//! Ranges of integers and sets of them.
+//!
+//! An [`Interval`] holds the integers between two bounds, both included, and
+//! answers containment and overlap from the bounds alone, so the size of the
+//! range costs nothing. A [`RangeSet`] holds any set of integers as the
+//! disjoint intervals that cover it, in order, and computes unions,
+//! intersections and differences by merging the interval lists.
+
+use std::{cmp::Ordering, fmt, ops::RangeInclusive};
+
+use itertools::Itertools;
+
+/// An integer type that intervals can be bounded by.
+pub trait Endpoint: Copy + Ord + fmt::Debug {
+    /// The next integer, if there is one.
+    fn succ(self) -> Option<Self>;
+    /// The previous integer, if there is one.
+    fn pred(self) -> Option<Self>;
+}
+
+macro_rules! endpoint {
+    ($($t:ty),*) => {$(
+        impl Endpoint for $t {
+            fn succ(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+
+            fn pred(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+        }
+    )*};
+}
+
+endpoint!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// The integers from `start` to `end`, both included. It is never empty.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T: Endpoint> Interval<T> {
+    /// Returns the interval from `start` to `end`, or `None` if `start` is
+    /// after `end`.
+    pub fn new(start: T, end: T) -> Option<Self> {
+        (start <= end).then_some(Interval { start, end })
+    }
+
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    pub fn contains(&self, n: T) -> bool {
+        self.start <= n && n <= self.end
+    }
+
+    /// Returns whether every integer of `other` is in this interval.
+    pub fn is_superset(&self, other: &Self) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        Self::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    /// Returns whether the two intervals overlap or are next to each other,
+    /// so that their union is an interval.
+    pub fn touches(&self, other: &Self) -> bool {
+        let before = |a: &Self, b: &Self| a.end.succ().is_some_and(|next| next < b.start);
+        !before(self, other) && !before(other, self)
+    }
+}
+
+impl<T: Endpoint> TryFrom<RangeInclusive<T>> for Interval<T> {
+    type Error = anyhow::Error;
+
+    fn try_from(range: RangeInclusive<T>) -> anyhow::Result<Self> {
+        let (start, end) = range.into_inner();
+        Interval::new(start, end)
+            .ok_or_else(|| anyhow::anyhow!("the range {start:?}..={end:?} is empty"))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Interval<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}..={:?}", self.start, self.end)
+    }
+}
+
+/// A set of integers, kept as the intervals that cover it in increasing
+/// order, with gaps between them.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct RangeSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T> Default for RangeSet<T> {
+    fn default() -> Self {
+        RangeSet { intervals: vec![] }
+    }
+}
+
+impl<T: Endpoint> RangeSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+
+    /// Returns whether `n` is in the set, in O(log n) of the intervals.
+    pub fn contains(&self, n: T) -> bool {
+        self.intervals
+            .binary_search_by(|interval| {
+                if interval.end < n {
+                    Ordering::Less
+                } else if interval.start > n {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+
+    /// Adds the integers of `interval` to the set.
+    pub fn insert(&mut self, interval: Interval<T>) {
+        *self = self.union(&RangeSet::from(interval));
+    }
+
+    /// Builds a set from intervals sorted by start, joining those that touch.
+    fn coalesce(sorted: impl IntoIterator<Item = Interval<T>>) -> Self {
+        let mut intervals: Vec<Interval<T>> = vec![];
+        for interval in sorted {
+            match intervals.last_mut() {
+                Some(last) if last.touches(&interval) => last.end = last.end.max(interval.end),
+                _ => intervals.push(interval),
+            }
+        }
+        RangeSet { intervals }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::coalesce(self.intervals.iter().merge(&other.intervals).copied())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let (a, b) = (&self.intervals, &other.intervals);
+        let (mut i, mut j) = (0, 0);
+        let mut intervals = vec![];
+        while i < a.len() && j < b.len() {
+            intervals.extend(a[i].intersection(&b[j]));
+            // The interval that ends first can't meet any later one
+            if a[i].end < b[j].end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        RangeSet { intervals }
+    }
+
+    /// Returns the integers of this set that aren't in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        let b = &other.intervals;
+        let mut j = 0;
+        let mut intervals = vec![];
+        for interval in &self.intervals {
+            while j < b.len() && b[j].end < interval.start {
+                j += 1;
+            }
+            // The start of the part of `interval` that is left
+            let mut rest = Some(interval.start);
+            for cut in b[j..].iter().take_while(|cut| cut.start <= interval.end) {
+                let Some(start) = rest else { break };
+                if let Some(before) = cut.start.pred() {
+                    intervals.extend(Interval::new(start, before));
+                }
+                rest = cut.end.succ();
+            }
+            if let Some(start) = rest {
+                intervals.extend(Interval::new(start, interval.end));
+            }
+        }
+        RangeSet { intervals }
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.difference(self).is_empty()
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        !self.intersection(other).is_empty()
+    }
+}
+
+impl<T: Endpoint> From<Interval<T>> for RangeSet<T> {
+    fn from(interval: Interval<T>) -> Self {
+        RangeSet {
+            intervals: vec![interval],
+        }
+    }
+}
+
+impl<T: Endpoint> FromIterator<Interval<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        Self::coalesce(iter.into_iter().sorted())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RangeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(&self.intervals).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    fn iv(start: i32, end: i32) -> Interval<i32> {
+        Interval::new(start, end).unwrap()
+    }
+
+    #[test]
+    fn compares_intervals() {
+        assert!(Interval::new(3, 2).is_none());
+        assert!(iv(2, 8).is_superset(&iv(3, 7)) && !iv(3, 7).is_superset(&iv(2, 8)));
+        assert!(iv(5, 7).overlaps(&iv(7, 9)) && !iv(2, 4).overlaps(&iv(6, 8)));
+        assert_eq!(iv(2, 6).intersection(&iv(4, 8)), Some(iv(4, 6)));
+        assert!(iv(1, 3).touches(&iv(4, 5)) && !iv(1, 3).touches(&iv(5, 5)));
+
+        // Only the bounds matter, however far apart they are
+        let huge = Interval::new(0, u64::MAX).unwrap();
+        assert!(huge.contains(u64::MAX / 2));
+        assert!(huge.is_superset(&Interval::new(1, u64::MAX - 1).unwrap()));
+        assert!(huge.touches(&huge));
+        let (start, end) = (5u64, 4);
+        assert!(Interval::try_from(start..=end).is_err());
+    }
+
+    /// The integers of a set in `0..64`
+    fn members(set: &RangeSet<i32>) -> BTreeSet<i32> {
+        (0..64).filter(|&n| set.contains(n)).collect()
+    }
+
+    #[test]
+    fn matches_sets_of_integers() {
+        let a = [iv(0, 3), iv(10, 20), iv(4, 6), iv(30, 40), iv(50, 50)]
+            .into_iter()
+            .collect::<RangeSet<_>>();
+        assert_eq!(
+            a.intervals(),
+            [iv(0, 6), iv(10, 20), iv(30, 40), iv(50, 50)]
+        );
+        let b = [iv(5, 12), iv(18, 35), iv(40, 60)]
+            .into_iter()
+            .collect::<RangeSet<_>>();
+
+        let (ma, mb) = (members(&a), members(&b));
+        let union = a.union(&b);
+        assert_eq!(members(&union), &ma | &mb);
+        assert_eq!(union.intervals(), [iv(0, 60)]);
+        assert_eq!(members(&a.intersection(&b)), &ma & &mb);
+        assert_eq!(members(&a.difference(&b)), &ma - &mb);
+        assert_eq!(members(&b.difference(&a)), &mb - &ma);
+        assert_eq!(
+            a.difference(&b).intervals(),
+            [iv(0, 4), iv(13, 17), iv(36, 39)]
+        );
+
+        assert!(union.is_superset(&a) && !a.is_superset(&union));
+        assert!(a.overlaps(&b) && !a.overlaps(&RangeSet::from(iv(7, 9))));
+
+        let mut set = RangeSet::new();
+        set.insert(iv(5, 6));
+        set.insert(iv(1, 2));
+        set.insert(iv(3, 4));
+        assert_eq!(set.intervals(), [iv(1, 6)]);
+    }
+
+    #[test]
+    fn handles_the_ends_of_the_type() {
+        let all = RangeSet::from(Interval::new(u8::MIN, u8::MAX).unwrap());
+        let ends = [
+            Interval::new(0, 0).unwrap(),
+            Interval::new(255, 255).unwrap(),
+        ]
+        .into_iter()
+        .collect::<RangeSet<u8>>();
+        let middle = all.difference(&ends);
+        assert_eq!(middle.intervals(), [Interval::new(1, 254).unwrap()]);
+        assert!(all.difference(&all).is_empty());
+        assert_eq!(middle.union(&ends), all);
+    }
+}
@@ -1 +1,23 @@
+//! Helpers shared by the daily solutions in `examples/`.
 
+pub mod alloc;
+pub mod batch;
+pub mod bitset;
+pub mod cancel;
+pub mod checkpoint;
+pub mod day;
+pub mod interval;
+pub mod memo;
+pub mod num;
+pub mod numeral;
+pub mod parse;
+pub mod phase;
+pub mod ranked;
+pub mod report;
+pub mod runner;
+pub mod search;
+pub mod snapshot;
+pub mod sparse;
+
+pub use day::Day;
+pub use phase::phase;
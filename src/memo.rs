@@ -0,0 +1,169 @@
+//! A memoization table for recursive solvers.
+//!
+//! A [`Memo`] maps subproblem keys to solved values and counts how often it
+//! was useful. A recursive function takes `&mut Memo` and either checks it
+//! by hand with [`Memo::get`] and [`Memo::insert`], or wraps its body in
+//! [`Memo::get_or_compute`].
+
+use std::{borrow::Borrow, collections::HashMap, fmt, hash::Hash};
+
+/// Hit/miss counters of a [`Memo`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub inserts: usize,
+    /// Number of times the table was flushed because it hit its capacity
+    pub flushes: usize,
+}
+
+impl MemoStats {
+    /// Returns the share of lookups that were hits, between 0 and 1.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+impl fmt::Display for MemoStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} hits ({:.1} %), {} misses, {} inserts, {} flushes",
+            self.hits,
+            self.hit_rate() * 100.,
+            self.misses,
+            self.inserts,
+            self.flushes
+        )
+    }
+}
+
+/// A keyed cache of solved subproblems with an optional capacity limit.
+pub struct Memo<K, V> {
+    map: HashMap<K, V>,
+    capacity: Option<usize>,
+    stats: MemoStats,
+}
+
+impl<K: Hash + Eq, V: Clone> Memo<K, V> {
+    /// Creates an unbounded table.
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+            capacity: None,
+            stats: MemoStats::default(),
+        }
+    }
+
+    /// Creates a table holding at most `capacity` entries. When an insert
+    /// would exceed the limit, the table is flushed and starts over.
+    pub fn with_capacity_limit(capacity: usize) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..Self::new()
+        }
+    }
+
+    /// Looks up `key`, counting the lookup as a hit or a miss.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.map.get(key) {
+            Some(value) => {
+                self.stats.hits += 1;
+                Some(value.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Stores the solution for `key`.
+    pub fn insert(&mut self, key: K, value: V) {
+        if let Some(capacity) = self.capacity {
+            if self.map.len() >= capacity && !self.map.contains_key(&key) {
+                self.map.clear();
+                self.stats.flushes += 1;
+            }
+        }
+        self.stats.inserts += 1;
+        self.map.insert(key, value);
+    }
+
+    /// Returns the cached value for `key`, or computes and caches it. The
+    /// table is passed on to `compute` so it can recurse.
+    pub fn get_or_compute(&mut self, key: K, compute: impl FnOnce(&mut Self) -> V) -> V {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = compute(self);
+        self.insert(key, value.clone());
+        value
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn stats(&self) -> MemoStats {
+        self.stats
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fib(n: u64, memo: &mut Memo<u64, u64>) -> u64 {
+        if n < 2 {
+            return n;
+        }
+        memo.get_or_compute(n, |memo| fib(n - 1, memo) + fib(n - 2, memo))
+    }
+
+    #[test]
+    fn memoizes_recursion() {
+        let mut memo = Memo::new();
+        assert_eq!(fib(90, &mut memo), 2880067194370816120);
+
+        let stats = memo.stats();
+        assert_eq!(stats.misses, 89);
+        assert_eq!(stats.hits, 87);
+        assert_eq!(memo.len(), 89);
+    }
+
+    #[test]
+    fn flushes_at_capacity() {
+        let mut memo = Memo::with_capacity_limit(2);
+        memo.insert(1, 'a');
+        memo.insert(2, 'b');
+        // Overwriting an existing key does not count against the limit
+        memo.insert(2, 'c');
+        assert_eq!(memo.stats().flushes, 0);
+
+        memo.insert(3, 'd');
+        assert_eq!(memo.stats().flushes, 1);
+        assert_eq!(memo.len(), 1);
+        assert_eq!(memo.get(&3), Some('d'));
+        assert_eq!(memo.get(&1), None);
+    }
+}
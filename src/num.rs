@@ -0,0 +1,124 @@
+//! Checked arithmetic on puzzle values that can grow large.
+//!
+//! [`Int`] is `i64`, or `i128` with the `wide-ints` feature. The operations
+//! here return an [`Overflow`] error where the plain operators would wrap in
+//! release builds or panic in debug builds, so a day whose values outgrow
+//! [`Int`] fails with the operation that overflowed.
+
+use std::fmt;
+
+/// The integer type of puzzle values.
+#[cfg(not(feature = "wide-ints"))]
+pub type Int = i64;
+/// The integer type of puzzle values.
+#[cfg(feature = "wide-ints")]
+pub type Int = i128;
+
+/// An arithmetic operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    /// The Euclidean remainder, which is never negative
+    Rem,
+}
+
+impl Op {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::Rem => "%",
+        }
+    }
+
+    /// Returns `lhs op rhs`, or an error if it doesn't fit in [`Int`] or
+    /// divides by zero.
+    pub fn apply(self, lhs: Int, rhs: Int) -> Result<Int, Overflow> {
+        let result = match self {
+            Op::Add => lhs.checked_add(rhs),
+            Op::Sub => lhs.checked_sub(rhs),
+            Op::Mul => lhs.checked_mul(rhs),
+            Op::Div => lhs.checked_div(rhs),
+            Op::Rem => lhs.checked_rem_euclid(rhs),
+        };
+        result.ok_or(Overflow { op: self, lhs, rhs })
+    }
+}
+
+/// An operation whose result doesn't fit in [`Int`], or a division by zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow {
+    pub op: Op,
+    pub lhs: Int,
+    pub rhs: Int,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Overflow { op, lhs, rhs } = self;
+        write!(f, "{lhs} {} {rhs} ", op.symbol())?;
+        if matches!(op, Op::Div | Op::Rem) && *rhs == 0 {
+            return f.write_str("divides by zero");
+        }
+        write!(f, "overflows {} bit integers", Int::BITS)?;
+        if !cfg!(feature = "wide-ints") {
+            f.write_str(", try the wide-ints feature")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+pub fn add(lhs: Int, rhs: Int) -> Result<Int, Overflow> {
+    Op::Add.apply(lhs, rhs)
+}
+
+pub fn sub(lhs: Int, rhs: Int) -> Result<Int, Overflow> {
+    Op::Sub.apply(lhs, rhs)
+}
+
+pub fn mul(lhs: Int, rhs: Int) -> Result<Int, Overflow> {
+    Op::Mul.apply(lhs, rhs)
+}
+
+pub fn div(lhs: Int, rhs: Int) -> Result<Int, Overflow> {
+    Op::Div.apply(lhs, rhs)
+}
+
+pub fn rem(lhs: Int, rhs: Int) -> Result<Int, Overflow> {
+    Op::Rem.apply(lhs, rhs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_overflow() {
+        assert_eq!(add(2, 3), Ok(5));
+        assert_eq!(rem(-7, 3), Ok(2));
+        assert_eq!(div(-7, 2), Ok(-3));
+
+        let err = mul(Int::MAX, 2).unwrap_err();
+        assert_eq!(err.op, Op::Mul);
+        assert!(
+            err.to_string()
+                .starts_with(&format!("{} * 2 overflows {} bit", Int::MAX, Int::BITS)),
+            "{err}"
+        );
+        assert!(sub(Int::MIN, 1).is_err());
+        assert!(div(Int::MIN, -1).is_err());
+    }
+
+    #[test]
+    fn reports_division_by_zero() {
+        assert_eq!(div(1, 0).unwrap_err().to_string(), "1 / 0 divides by zero");
+        assert_eq!(rem(1, 0).unwrap_err().to_string(), "1 % 0 divides by zero");
+    }
+}
@@ -0,0 +1,379 @@
+//! Numbers written with the digits of a positional system, of any size.
+//!
+//! A [`System`] gives the symbols of the digits, the value of the lowest one
+//! and the base of each place, so it covers ordinary bases, balanced bases
+//! like the base 5 with digits -2 to 2 of day 25, and mixed radixes where
+//! each place has its own base. A [`Numeral`] keeps the digits of a number
+//! in such a system and parses, formats, adds, subtracts, multiplies and
+//! compares them place by place, carrying between places, so it never
+//! converts to a machine integer and has no limit on size.
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::Sum,
+    marker::PhantomData,
+    ops::{Add, Mul},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail};
+
+/// A positional numeral system.
+///
+/// Every radix must be at least 2 and at most the number of symbols, and the
+/// digits of each place, from `LOWEST` up to `LOWEST + radix - 1`, must
+/// include 0. Systems whose lowest digit is 0 can't write negative numbers.
+pub trait System {
+    /// The symbols of the digits, from the lowest value up
+    const SYMBOLS: &'static str;
+    /// The value of the first symbol
+    const LOWEST: i32;
+    /// The base of each place, from the least significant up. Places past
+    /// the end use the last base.
+    const RADICES: &'static [u32];
+}
+
+fn radix<S: System>(place: usize) -> i128 {
+    let radix = S::RADICES.get(place).or(S::RADICES.last());
+    *radix.expect("a system has at least one radix") as i128
+}
+
+/// Turns the values of the places, least significant first, into digits by
+/// carrying the excess of each place into the next. Returns `None` if the
+/// number needs infinitely many digits, like a negative number in a system
+/// without negative digits.
+fn normalize<S: System>(values: impl IntoIterator<Item = i128>) -> Option<Vec<i32>> {
+    let lowest = S::LOWEST as i128;
+    let mut values = values.into_iter();
+    let mut digits = vec![];
+    let mut carry = 0;
+    loop {
+        let place = digits.len();
+        let (value, exhausted) = match values.next() {
+            Some(value) => (value + carry, false),
+            None if carry == 0 => break,
+            None => (carry, true),
+        };
+        let base = radix::<S>(place);
+        // value = quot * base + rem = digit + next * base, computed without
+        // overflowing on the way
+        let (quot, rem) = (value.div_euclid(base), value.rem_euclid(base));
+        let digit = lowest + (rem - lowest).rem_euclid(base);
+        let next = quot - (digit - rem) / base;
+        // Past the values and the listed bases, a carry that doesn't shrink
+        // never will
+        if exhausted && next == carry && place + 1 >= S::RADICES.len() {
+            return None;
+        }
+        digits.push(digit as i32);
+        carry = next;
+    }
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    Some(digits)
+}
+
+/// A number in the system `S`.
+pub struct Numeral<S> {
+    /// Least significant first, without leading zeros
+    digits: Vec<i32>,
+    system: PhantomData<S>,
+}
+
+impl<S: System> Numeral<S> {
+    fn from_digits(digits: Vec<i32>) -> Self {
+        Numeral {
+            digits,
+            system: PhantomData,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::from_digits(vec![])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// Returns -1, 0 or 1 for negative, zero and positive numbers. The
+    /// leading digit outweighs all places below it, so it decides.
+    pub fn signum(&self) -> i32 {
+        self.digits.last().map_or(0, |digit| digit.signum())
+    }
+
+    /// Returns `n` in this system, or `None` if it can't be written in it.
+    pub fn from_int(n: i128) -> Option<Self> {
+        normalize::<S>([n]).map(Self::from_digits)
+    }
+
+    /// Returns the value as an `i128`, or `None` if it doesn't fit.
+    pub fn to_int(&self) -> Option<i128> {
+        let mut n: i128 = 0;
+        for (place, &digit) in self.digits.iter().enumerate().rev() {
+            let (base, digit) = (radix::<S>(place), digit as i128);
+            // Where the digit has the other sign, `n` lends it one unit of
+            // this place first, so that numbers near the ends of the range
+            // don't overflow on the way
+            let lend = match (n.signum(), digit.signum()) {
+                (-1, 1) => 1,
+                (1, -1) => -1,
+                _ => 0,
+            };
+            n = (n + lend)
+                .checked_mul(base)?
+                .checked_add(digit - lend * base)?;
+        }
+        Some(n)
+    }
+
+    /// Returns `-self`, or `None` if it can't be written in this system.
+    pub fn checked_neg(&self) -> Option<Self> {
+        let values = self.digits.iter().map(|&digit| -(digit as i128));
+        normalize::<S>(values).map(Self::from_digits)
+    }
+
+    /// Returns `self - other`, or `None` if it can't be written in this
+    /// system.
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.zip_with(other, |a, b| a - b)
+    }
+
+    /// Returns `self * factor` for a `factor` about the size of a digit, so
+    /// that the places can't overflow.
+    fn scale(&self, factor: i128) -> Option<Self> {
+        let values = self.digits.iter().map(|&digit| digit as i128 * factor);
+        normalize::<S>(values).map(Self::from_digits)
+    }
+
+    fn zip_with(&self, other: &Self, f: impl Fn(i128, i128) -> i128) -> Option<Self> {
+        let len = self.digits.len().max(other.digits.len());
+        let digit = |digits: &[i32], place| digits.get(place).copied().unwrap_or(0) as i128;
+        let values =
+            (0..len).map(|place| f(digit(&self.digits, place), digit(&other.digits, place)));
+        normalize::<S>(values).map(Self::from_digits)
+    }
+}
+
+impl<S: System> FromStr for Numeral<S> {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            bail!("empty numeral");
+        }
+        let mut digits = s
+            .chars()
+            .rev()
+            .enumerate()
+            .map(|(place, c)| {
+                let index = S::SYMBOLS
+                    .chars()
+                    .position(|symbol| symbol == c)
+                    .filter(|&index| (index as i128) < radix::<S>(place))
+                    .ok_or_else(|| anyhow!("invalid digit {c:?} in {s:?}"))?;
+                Ok(S::LOWEST + index as i32)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Ok(Self::from_digits(digits))
+    }
+}
+
+impl<S: System> fmt::Display for Numeral<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = |digit: i32| {
+            let index = (digit - S::LOWEST) as usize;
+            S::SYMBOLS
+                .chars()
+                .nth(index)
+                .expect("a symbol for each digit")
+        };
+        if self.is_zero() {
+            return write!(f, "{}", symbol(0));
+        }
+        let s = self.digits.iter().rev().map(|&digit| symbol(digit));
+        f.write_str(&s.collect::<String>())
+    }
+}
+
+impl<S: System> fmt::Debug for Numeral<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Numeral({self})")
+    }
+}
+
+impl<S: System> Add for Numeral<S> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.zip_with(&other, |a, b| a + b)
+            .expect("the sum of two numbers in a system is in the system")
+    }
+}
+
+impl<S: System> Mul for Numeral<S> {
+    type Output = Self;
+
+    /// Schoolbook multiplication: one partial product of `self` per digit of
+    /// `other`, from the most significant down, shifting the running total
+    /// up a place in between. Shifting multiplies by the base of the place,
+    /// which is what makes it work for mixed radixes too.
+    fn mul(self, other: Self) -> Self {
+        let product = other.digits.iter().enumerate().rev().try_fold(
+            Self::zero(),
+            |total, (place, &digit)| {
+                let shifted = total.scale(radix::<S>(place))?;
+                Some(shifted + self.scale(digit as i128)?)
+            },
+        );
+        product.expect("the product of two numbers in a system is in the system")
+    }
+}
+
+impl<S: System> Sum for Numeral<S> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl<S: System> Default for Numeral<S> {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<S> Clone for Numeral<S> {
+    fn clone(&self) -> Self {
+        Numeral {
+            digits: self.digits.clone(),
+            system: PhantomData,
+        }
+    }
+}
+
+impl<S> PartialEq for Numeral<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.digits == other.digits
+    }
+}
+
+impl<S> Eq for Numeral<S> {}
+
+impl<S> Hash for Numeral<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.digits.hash(state);
+    }
+}
+
+impl<S: System> Ord for Numeral<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Only a negative difference can be impossible to write
+        match self.checked_sub(other) {
+            Some(diff) => diff.signum().cmp(&0),
+            None => Ordering::Less,
+        }
+    }
+}
+
+impl<S: System> PartialOrd for Numeral<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Balanced ternary
+    struct Ternary;
+
+    impl System for Ternary {
+        const SYMBOLS: &'static str = "-0+";
+        const LOWEST: i32 = -1;
+        const RADICES: &'static [u32] = &[3];
+    }
+
+    /// The factorial number system, which runs out of symbols at 10 places
+    struct Factoradic;
+
+    impl System for Factoradic {
+        const SYMBOLS: &'static str = "0123456789";
+        const LOWEST: i32 = 0;
+        const RADICES: &'static [u32] = &[2, 3, 4, 5, 6, 7, 8, 9, 10];
+    }
+
+    type T = Numeral<Ternary>;
+    type F = Numeral<Factoradic>;
+
+    #[test]
+    fn balanced_base() {
+        let t = |n| T::from_int(n).unwrap();
+        assert_eq!(t(8).to_string(), "+0-");
+        assert_eq!(t(-8).to_string(), "-0+");
+        assert_eq!(t(0).to_string(), "0");
+        assert_eq!("00+-".parse::<T>().unwrap(), t(2));
+        assert!("+2".parse::<T>().is_err());
+
+        assert_eq!(t(8) + t(-13), t(-5));
+        assert_eq!(t(8).checked_sub(&t(13)), Some(t(-5)));
+        assert_eq!(t(8).checked_neg(), Some(t(-8)));
+        assert!(t(-1) < t(0) && t(7) > t(-20));
+        for n in [i128::MIN, i128::MAX, -1, 1] {
+            assert_eq!(t(n).to_int(), Some(n));
+        }
+
+        // Far past 128 bits
+        let big = "+".repeat(100).parse::<T>().unwrap();
+        assert_eq!(big.to_int(), None);
+        let sum = big.clone() + big.clone() + big.clone();
+        assert_eq!(sum.to_string(), "+".repeat(100) + "0");
+        assert_eq!(
+            sum.checked_sub(&big).unwrap().checked_sub(&big),
+            Some(big.clone())
+        );
+
+        assert_eq!(t(8) * t(-13), t(-104));
+        assert_eq!(t(-7) * t(-7), t(49));
+        assert_eq!(t(5) * t(0), t(0));
+        for (a, b) in [(i64::MIN as i128, i64::MAX as i128), (-1, i128::MAX)] {
+            assert_eq!((t(a) * t(b)).to_int(), Some(a * b));
+        }
+        // 3^100 from (3^100 - 1) / 2, squared
+        let power = big.clone() * t(2) + t(1);
+        assert_eq!(power.to_string(), "+".to_string() + &"0".repeat(100));
+        let square = power.clone() * power;
+        assert_eq!(square.to_string(), "+".to_string() + &"0".repeat(200));
+        let even = big * t(2);
+        let below = even.clone() * (even + t(2));
+        assert_eq!(below, square.checked_sub(&t(1)).unwrap());
+    }
+
+    #[test]
+    fn mixed_radix() {
+        let f = |n| F::from_int(n).unwrap();
+        assert_eq!(f(463).to_string(), "34101");
+        assert_eq!("34101".parse::<F>().unwrap().to_int(), Some(463));
+        // The digit of the place with base 2 is at most 1
+        assert!("2".parse::<F>().is_err());
+        assert_eq!(f(5) + f(1), f(6));
+        assert_eq!(f(6).to_string(), "100");
+        assert_eq!(f(6) * f(7), f(42));
+        assert_eq!(f(719) * f(719), f(719 * 719));
+        assert_eq!(f(3) * f(0), f(0));
+
+        // There are no negative numbers without negative digits
+        assert_eq!(F::from_int(-1), None);
+        assert_eq!(f(3).checked_sub(&f(4)), None);
+        assert_eq!(f(4).checked_sub(&f(3)), Some(f(1)));
+        assert_eq!(f(720).checked_sub(&f(1)), Some(f(719)));
+        assert!(f(3) < f(4) && f(4) > f(3));
+        assert_eq!(f(0).checked_neg(), Some(f(0)));
+    }
+}
@@ -0,0 +1,513 @@
+//! Parser combinators for the input shapes that come up again and again.
+//!
+//! A parser is anything that implements [`Parser`], which includes every
+//! `Fn(Input) -> PResult<T>`. The functions below parse numbers, tags, words,
+//! ranges and character maps, the [`Parser`] methods combine them, and
+//! [`lines`] and [`blocks`] repeat them. [`parse_all`] runs a parser over a
+//! whole puzzle input and reports the line and column where it failed.
+//!
+//! ```
+//! use aoc::parse::{blocks, lines, parse_all, uint};
+//!
+//! let elves = parse_all(blocks(lines(uint::<u32>())), "1\n2\n\n3\n").unwrap();
+//! assert_eq!(elves, [vec![1, 2], vec![3]]);
+//!
+//! let err = parse_all(blocks(lines(uint::<u32>())), "1\nx\n").unwrap_err();
+//! assert_eq!(err.to_string(), "line 2, column 1: expected a number, found \"x\"");
+//! ```
+
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+/// The rest of the text to parse, along with where it is in the whole text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Input<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Input<'a> {
+    pub fn new(src: &'a str) -> Input<'a> {
+        Input { src, pos: 0 }
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rest().is_empty()
+    }
+
+    /// Splits off the first `len` bytes of the rest.
+    fn take(self, len: usize) -> (&'a str, Input<'a>) {
+        let taken = &self.rest()[..len];
+        let rest = Input {
+            pos: self.pos + len,
+            ..self
+        };
+        (taken, rest)
+    }
+
+    /// Returns an error that says `expected` was expected here.
+    pub fn error(&self, expected: impl fmt::Display) -> ParseError {
+        let before = &self.src[..self.pos];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let rest = self.rest();
+        let found = match rest.chars().next() {
+            None => "end of input".to_string(),
+            Some('\n' | '\r') => "end of line".to_string(),
+            Some(c) if c.is_whitespace() => format!("{c:?}"),
+            Some(_) => {
+                let token = rest.split(char::is_whitespace).next().unwrap_or(rest);
+                let token = token
+                    .char_indices()
+                    .nth(12)
+                    .map_or(token, |(i, _)| &token[..i]);
+                format!("{token:?}")
+            }
+        };
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            expected: expected.to_string(),
+            found,
+            offset: self.pos,
+        }
+    }
+}
+
+/// Where and why parsing failed. Lines and columns count from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub expected: String,
+    pub found: String,
+    offset: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}, found {}",
+            self.line, self.column, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The parsed value and the input after it, or where parsing failed.
+pub type PResult<'a, T> = Result<(T, Input<'a>), ParseError>;
+
+pub trait Parser<'a, T> {
+    fn parse(&self, input: Input<'a>) -> PResult<'a, T>;
+
+    fn map<U>(self, f: impl Fn(T) -> U) -> impl Parser<'a, U>
+    where
+        Self: Sized,
+    {
+        move |input: Input<'a>| {
+            let (value, rest) = self.parse(input)?;
+            Ok((f(value), rest))
+        }
+    }
+
+    /// Maps the value with `f`, failing with "expected `expected`" where the
+    /// value started if `f` returns `None`.
+    fn try_map<U>(self, expected: &'static str, f: impl Fn(T) -> Option<U>) -> impl Parser<'a, U>
+    where
+        Self: Sized,
+    {
+        move |input: Input<'a>| {
+            let (value, rest) = self.parse(input)?;
+            match f(value) {
+                Some(value) => Ok((value, rest)),
+                None => Err(input.error(expected)),
+            }
+        }
+    }
+
+    /// Parses `self` and then `next`, and keeps both values.
+    fn then<U>(self, next: impl Parser<'a, U>) -> impl Parser<'a, (T, U)>
+    where
+        Self: Sized,
+    {
+        move |input: Input<'a>| {
+            let (first, rest) = self.parse(input)?;
+            let (second, rest) = next.parse(rest)?;
+            Ok(((first, second), rest))
+        }
+    }
+
+    /// Parses `self` and then `next`, and keeps the value of `self`.
+    fn skip<U>(self, next: impl Parser<'a, U>) -> impl Parser<'a, T>
+    where
+        Self: Sized,
+    {
+        self.then(next).map(|(value, _)| value)
+    }
+
+    /// Parses `self` if it can, without consuming anything otherwise.
+    fn opt(self) -> impl Parser<'a, Option<T>>
+    where
+        Self: Sized,
+    {
+        move |input: Input<'a>| match self.parse(input) {
+            Ok((value, rest)) => Ok((Some(value), rest)),
+            Err(_) => Ok((None, input)),
+        }
+    }
+
+    /// Parses `self`, or `other` if `self` fails.
+    fn or(self, other: impl Parser<'a, T>) -> impl Parser<'a, T>
+    where
+        Self: Sized,
+    {
+        move |input: Input<'a>| {
+            let first = match self.parse(input) {
+                Ok(ok) => return Ok(ok),
+                Err(e) => e,
+            };
+            other.parse(input).map_err(|second| {
+                // Report the alternative that got further
+                match first.offset.cmp(&second.offset) {
+                    std::cmp::Ordering::Greater => first,
+                    std::cmp::Ordering::Less => second,
+                    std::cmp::Ordering::Equal => ParseError {
+                        expected: format!("{} or {}", first.expected, second.expected),
+                        ..second
+                    },
+                }
+            })
+        }
+    }
+
+    /// Parses one or more `self` in a row.
+    fn many1(self) -> impl Parser<'a, Vec<T>>
+    where
+        Self: Sized,
+    {
+        move |input: Input<'a>| {
+            let (first, mut rest) = self.parse(input)?;
+            let mut items = vec![first];
+            while let Ok((item, after)) = self.parse(rest) {
+                items.push(item);
+                rest = after;
+            }
+            Ok((items, rest))
+        }
+    }
+
+    /// Parses one or more `self` separated by `sep`. An item must follow
+    /// every separator.
+    fn sep_by<U>(self, sep: impl Parser<'a, U>) -> impl Parser<'a, Vec<T>>
+    where
+        Self: Sized,
+    {
+        move |input: Input<'a>| {
+            let (first, mut rest) = self.parse(input)?;
+            let mut items = vec![first];
+            while let Ok((_, after_sep)) = sep.parse(rest) {
+                let (item, after_item) = self.parse(after_sep)?;
+                items.push(item);
+                rest = after_item;
+            }
+            Ok((items, rest))
+        }
+    }
+}
+
+impl<'a, T, F> Parser<'a, T> for F
+where
+    F: Fn(Input<'a>) -> PResult<'a, T>,
+{
+    fn parse(&self, input: Input<'a>) -> PResult<'a, T> {
+        self(input)
+    }
+}
+
+/// Parses `first` and then `second`, and keeps the value of `second`.
+pub fn preceded<'a, T, U>(
+    first: impl Parser<'a, T>,
+    second: impl Parser<'a, U>,
+) -> impl Parser<'a, U> {
+    first.then(second).map(|(_, value)| value)
+}
+
+/// Parses the text `tag`.
+pub fn tag<'a>(tag: &'static str) -> impl Parser<'a, &'a str> {
+    move |input: Input<'a>| {
+        if input.rest().starts_with(tag) {
+            Ok(input.take(tag.len()))
+        } else {
+            Err(input.error(format!("{tag:?}")))
+        }
+    }
+}
+
+/// Parses one or more characters that match `pred`.
+pub fn take_while1<'a>(
+    expected: &'static str,
+    pred: impl Fn(char) -> bool,
+) -> impl Parser<'a, &'a str> {
+    move |input: Input<'a>| {
+        let rest = input.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(input.error(expected));
+        }
+        Ok(input.take(len))
+    }
+}
+
+/// Parses zero or more spaces or tabs.
+pub fn ws<'a>() -> impl Parser<'a, ()> {
+    take_while1("", |c| c == ' ' || c == '\t').opt().map(|_| ())
+}
+
+/// Parses a run of letters and digits.
+pub fn word<'a>() -> impl Parser<'a, &'a str> {
+    take_while1("a word", char::is_alphanumeric)
+}
+
+/// Parses the rest of the line, which may be empty, but not the newline.
+pub fn rest_of_line<'a>() -> impl Parser<'a, &'a str> {
+    move |input: Input<'a>| {
+        let rest = input.rest();
+        Ok(input.take(rest.find(['\r', '\n']).unwrap_or(rest.len())))
+    }
+}
+
+/// Parses an unsigned number.
+pub fn uint<'a, T: FromStr>() -> impl Parser<'a, T> {
+    take_while1("a number", |c| c.is_ascii_digit()).try_map("a number", |s| s.parse().ok())
+}
+
+/// Parses a number with an optional sign.
+pub fn int<'a, T: FromStr>() -> impl Parser<'a, T> {
+    move |input: Input<'a>| {
+        let sign = input.rest().starts_with(['-', '+']) as usize;
+        let rest = input.rest();
+        let digits = rest[sign..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len() - sign);
+        match rest[..sign + digits].parse() {
+            Ok(num) if digits > 0 => Ok((num, input.take(sign + digits).1)),
+            _ => Err(input.error("a number")),
+        }
+    }
+}
+
+/// Parses an inclusive range like `2-8`.
+pub fn range<'a, T: FromStr>() -> impl Parser<'a, RangeInclusive<T>> {
+    uint()
+        .skip(tag("-"))
+        .then(uint())
+        .map(|(start, end)| start..=end)
+}
+
+/// Parses the rest of the line and returns the signed numbers in it, for
+/// numbers embedded in prose like `Sensor at x=2, y=-18`.
+pub fn ints_in<'a, T: FromStr>() -> impl Parser<'a, Vec<T>> {
+    move |input: Input<'a>| {
+        let (line, rest) = rest_of_line().parse(input)?;
+        let mut nums = vec![];
+        let mut at = input;
+        while !at.is_empty() && at.pos < rest.pos {
+            let after_sign = at.rest().trim_start_matches('-');
+            if after_sign.starts_with(|c: char| c.is_ascii_digit()) {
+                let (num, after) = int().parse(at)?;
+                nums.push(num);
+                at = after;
+            } else {
+                let len = at.rest().chars().next().map_or(1, char::len_utf8);
+                at = at.take(len).1;
+            }
+        }
+        if nums.is_empty() {
+            return Err(input.error(format!("numbers in {line:?}")));
+        }
+        Ok((nums, rest))
+    }
+}
+
+/// Parses `key`, the separator `sep` and `value`, like `root: pppw + sjmn`.
+pub fn key_value<'a, K, V>(
+    key: impl Parser<'a, K>,
+    sep: &'static str,
+    value: impl Parser<'a, V>,
+) -> impl Parser<'a, (K, V)> {
+    key.skip(tag(sep)).then(value)
+}
+
+/// Returns whether `rest` starts at the end of a line, `\n` or `\r\n`, or
+/// of the text.
+fn at_line_end(rest: &str) -> bool {
+    rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n") || rest == "\r"
+}
+
+/// Parses one or more lines of `item`, ending in `\n` or `\r\n`. Stops
+/// before a blank line, which [`blocks`] uses to separate groups of lines.
+pub fn lines<'a, T>(item: impl Parser<'a, T>) -> impl Parser<'a, Vec<T>> {
+    sep_by_until(item, &["\n", "\r\n"], at_line_end)
+}
+
+/// Parses one or more groups of `item` separated by blank lines.
+pub fn blocks<'a, T>(item: impl Parser<'a, T>) -> impl Parser<'a, Vec<T>> {
+    sep_by_until(item, &["\n\n", "\r\n\r\n"], |rest| rest.trim().is_empty())
+}
+
+/// Parses `item` separated by any of `seps`, until what follows a separator
+/// is `end`.
+fn sep_by_until<'a, T>(
+    item: impl Parser<'a, T>,
+    seps: &'static [&'static str],
+    end: impl Fn(&str) -> bool,
+) -> impl Parser<'a, Vec<T>> {
+    move |input: Input<'a>| {
+        let (first, mut rest) = item.parse(input)?;
+        let mut items = vec![first];
+        while let Some(sep) = seps.iter().find(|sep| rest.rest().starts_with(*sep)) {
+            let after_sep = rest.take(sep.len()).1;
+            if end(after_sep.rest()) {
+                break;
+            }
+            let (next, after_item) = item.parse(after_sep)?;
+            items.push(next);
+            rest = after_item;
+        }
+        Ok((items, rest))
+    }
+}
+
+/// Parses a map with one character per cell, like `#..#`, up to a blank line.
+/// `cell` returns `None` for characters that can't be in the map.
+pub fn grid<'a, T>(
+    expected: &'static str,
+    cell: impl Fn(char) -> Option<T>,
+) -> impl Parser<'a, Vec<Vec<T>>> {
+    let row = move |input: Input<'a>| {
+        let mut row = vec![];
+        let mut at = input;
+        while let Some(c) = at.rest().chars().next().filter(|_| !at_line_end(at.rest())) {
+            row.push(cell(c).ok_or_else(|| at.error(expected))?);
+            at = at.take(c.len_utf8()).1;
+        }
+        if row.is_empty() {
+            return Err(at.error(expected));
+        }
+        Ok((row, at))
+    };
+    lines(row)
+}
+
+/// Parses all of `src` with `parser`, allowing trailing whitespace.
+pub fn parse_all<'a, T>(parser: impl Parser<'a, T>, src: &'a str) -> Result<T, ParseError> {
+    let (value, rest) = parser.parse(Input::new(src))?;
+    let trailing = rest.rest().len() - rest.rest().trim_start().len();
+    let (_, rest) = rest.take(trailing);
+    if !rest.is_empty() {
+        return Err(rest.error("end of input"));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_positions() {
+        let err = parse_all(
+            lines(range::<u32>().skip(tag(",")).then(range::<u32>())),
+            "2-4,6-8\n2-3,x",
+        )
+        .unwrap_err();
+        assert_eq!((err.line, err.column), (2, 5));
+        assert_eq!(err.expected, "a number");
+        assert_eq!(err.found, "\"x\"");
+
+        let err = parse_all(uint::<u8>(), "300").unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.found.as_str()),
+            (1, 1, "\"300\"")
+        );
+
+        let err = parse_all(tag("a").or(tag("b")), "c").unwrap_err();
+        assert_eq!(err.expected, "\"a\" or \"b\"");
+    }
+
+    #[test]
+    fn groups_and_lines() {
+        let input = "1\n2\n\n3\n\n\n";
+        assert_eq!(
+            parse_all(blocks(lines(uint::<u32>())), input).unwrap(),
+            [vec![1, 2], vec![3]]
+        );
+        assert_eq!(parse_all(lines(word()), "ab\ncd").unwrap(), ["ab", "cd"]);
+        let moves = uint::<u32>().map(Some).or(tag("L").map(|_| None)).many1();
+        assert_eq!(parse_all(moves, "10L5").unwrap(), [Some(10), None, Some(5)]);
+        assert!(parse_all(lines(uint::<u32>()), "1\n\n2").is_err());
+    }
+
+    #[test]
+    fn numbers_in_prose() {
+        let line = "Sensor at x=2, y=-18: closest beacon is at x=-2, y=15";
+        assert_eq!(parse_all(ints_in::<i64>(), line).unwrap(), [2, -18, -2, 15]);
+        assert_eq!(parse_all(int::<i32>(), "-7").unwrap(), -7);
+        assert!(parse_all(int::<i32>(), "-").is_err());
+        assert!(parse_all(ints_in::<i32>(), "none here").is_err());
+    }
+
+    #[test]
+    fn key_values_and_lists() {
+        let job = key_value(word(), ": ", word().sep_by(tag(" + ")));
+        assert_eq!(
+            parse_all(job, "root: pppw + sjmn").unwrap(),
+            ("root", vec!["pppw", "sjmn"])
+        );
+    }
+
+    #[test]
+    fn character_maps() {
+        fn map<'a>() -> impl Parser<'a, Vec<Vec<bool>>> {
+            grid("'#' or '.'", |c| match c {
+                '#' => Some(true),
+                '.' => Some(false),
+                _ => None,
+            })
+        }
+        assert_eq!(
+            parse_all(map(), "#.\n.#\n").unwrap(),
+            [[true, false], [false, true]]
+        );
+        let err = parse_all(map(), "#.\n.x").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let input = "1\r\n2\r\n\r\n3\r\n";
+        assert_eq!(
+            parse_all(blocks(lines(uint::<u32>())), input).unwrap(),
+            [vec![1, 2], vec![3]]
+        );
+        assert_eq!(
+            parse_all(lines(word()), "ab\r\ncd\r\n").unwrap(),
+            ["ab", "cd"]
+        );
+        let map = grid("'#' or '.'", |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        });
+        assert_eq!(
+            parse_all(map, "#.\r\n.#\r").unwrap(),
+            [[true, false], [false, true]]
+        );
+        let err = parse_all(lines(uint::<u32>()), "1\r\nx\r\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+    }
+}
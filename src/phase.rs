@@ -0,0 +1,61 @@
+//! Timing and allocation accounting for the phases of a solution.
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::alloc::{self, AllocStats};
+
+/// The measurements taken for one phase.
+#[derive(Clone, Copy, Debug)]
+pub struct PhaseReport {
+    pub elapsed: Duration,
+    pub alloc: AllocStats,
+}
+
+impl fmt::Display for PhaseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2?}", self.elapsed)?;
+        if alloc::ENABLED {
+            write!(
+                f,
+                ", {} allocs, {} allocated, peak {}",
+                self.alloc.allocs,
+                alloc::fmt_bytes(self.alloc.bytes),
+                alloc::fmt_bytes(self.alloc.peak)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `f` and returns its result along with the time and allocations it
+/// took.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, PhaseReport) {
+    let before = alloc::stats();
+    alloc::reset_peak();
+    let start = Instant::now();
+
+    let ret = f();
+
+    let elapsed = start.elapsed();
+    let report = PhaseReport {
+        elapsed,
+        alloc: alloc::stats().since(&before),
+    };
+    (ret, report)
+}
+
+/// Runs `f` as the phase `name` and reports its measurements on stderr.
+pub fn phase<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let (ret, report) = measure(f);
+    eprintln!("[{name}] {report}");
+    ret
+}
+
+/// Reports an extra measurement, such as cache statistics, under the phase
+/// `name`.
+pub fn note(name: &str, what: impl fmt::Display) {
+    eprintln!("[{name}] {what}");
+}
@@ -0,0 +1,241 @@
+//! An ordered map that knows the position of its keys.
+//!
+//! A [`RankedMap`] maps ordered keys to `u64` values like a `BTreeMap`, and
+//! also finds the rank of a key, the key at a rank and the sum of the values
+//! of the first keys in expected logarithmic time. It is a treap: a binary
+//! search tree by key that is a heap by random priority, where each node
+//! keeps the size and the value sum of its subtree.
+
+use std::cmp::Ordering;
+
+type Tree<K> = Option<Box<Node<K>>>;
+
+struct Node<K> {
+    key: K,
+    value: u64,
+    priority: u64,
+    /// The number of nodes and the sum of their values in this subtree
+    len: usize,
+    sum: u64,
+    left: Tree<K>,
+    right: Tree<K>,
+}
+
+fn len<K>(tree: &Tree<K>) -> usize {
+    tree.as_ref().map_or(0, |node| node.len)
+}
+
+fn sum<K>(tree: &Tree<K>) -> u64 {
+    tree.as_ref().map_or(0, |node| node.sum)
+}
+
+impl<K> Node<K> {
+    fn update(&mut self) {
+        self.len = len(&self.left) + 1 + len(&self.right);
+        self.sum = sum(&self.left) + self.value + sum(&self.right);
+    }
+}
+
+/// Splits `tree` into the nodes whose keys are `left` of the split and the
+/// rest. `left` must hold for a prefix of the keys.
+fn split<K>(tree: Tree<K>, left: &impl Fn(&K) -> bool) -> (Tree<K>, Tree<K>) {
+    let Some(mut node) = tree else {
+        return (None, None);
+    };
+    if left(&node.key) {
+        let (l, r) = split(node.right.take(), left);
+        node.right = l;
+        node.update();
+        (Some(node), r)
+    } else {
+        let (l, r) = split(node.left.take(), left);
+        node.left = r;
+        node.update();
+        (l, Some(node))
+    }
+}
+
+/// Joins two trees where all keys of `a` are before those of `b`.
+fn merge<K>(a: Tree<K>, b: Tree<K>) -> Tree<K> {
+    match (a, b) {
+        (None, tree) | (tree, None) => tree,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority >= b.priority {
+                a.right = merge(a.right.take(), Some(b));
+                a.update();
+                Some(a)
+            } else {
+                b.left = merge(Some(a), b.left.take());
+                b.update();
+                Some(b)
+            }
+        }
+    }
+}
+
+/// A map from ordered keys to `u64` values with rank queries.
+pub struct RankedMap<K> {
+    root: Tree<K>,
+    /// The state of the xorshift generator of priorities, which needn't be
+    /// unpredictable, only spread out
+    seed: u64,
+}
+
+impl<K> Default for RankedMap<K> {
+    fn default() -> Self {
+        RankedMap {
+            root: None,
+            seed: 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+}
+
+impl<K: Ord> RankedMap<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// The sum of all values
+    pub fn total(&self) -> u64 {
+        sum(&self.root)
+    }
+
+    pub fn get(&self, key: &K) -> Option<u64> {
+        let mut tree = &self.root;
+        while let Some(node) = tree {
+            tree = match key.cmp(&node.key) {
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right,
+                Ordering::Equal => return Some(node.value),
+            };
+        }
+        None
+    }
+
+    /// Sets the value of `key`, returning its old value.
+    pub fn insert(&mut self, key: K, value: u64) -> Option<u64> {
+        let old = self.remove(&key);
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        let node = Box::new(Node {
+            key,
+            value,
+            priority: self.seed,
+            len: 1,
+            sum: value,
+            left: None,
+            right: None,
+        });
+        let (before, after) = split(self.root.take(), &|k| k < &node.key);
+        self.root = merge(merge(before, Some(node)), after);
+        old
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove(&mut self, key: &K) -> Option<u64> {
+        let (before, rest) = split(self.root.take(), &|k| k < key);
+        let (found, after) = split(rest, &|k| k == key);
+        self.root = merge(before, after);
+        found.map(|node| node.value)
+    }
+
+    /// Returns the number of keys before `key`, if it is in the map.
+    pub fn rank(&self, key: &K) -> Option<usize> {
+        let mut rank = 0;
+        let mut tree = &self.root;
+        while let Some(node) = tree {
+            tree = match key.cmp(&node.key) {
+                Ordering::Less => &node.left,
+                Ordering::Greater => {
+                    rank += len(&node.left) + 1;
+                    &node.right
+                }
+                Ordering::Equal => return Some(rank + len(&node.left)),
+            };
+        }
+        None
+    }
+
+    /// Returns the key with `rank` keys before it, and its value.
+    pub fn nth(&self, mut rank: usize) -> Option<(&K, u64)> {
+        let mut tree = &self.root;
+        while let Some(node) = tree {
+            let left = len(&node.left);
+            tree = match rank.cmp(&left) {
+                Ordering::Less => &node.left,
+                Ordering::Greater => {
+                    rank -= left + 1;
+                    &node.right
+                }
+                Ordering::Equal => return Some((&node.key, node.value)),
+            };
+        }
+        None
+    }
+
+    /// Returns the sum of the values of the first `k` keys, or of all keys
+    /// if there are fewer.
+    pub fn sum_first(&self, mut k: usize) -> u64 {
+        let mut total = 0;
+        let mut tree = &self.root;
+        while let Some(node) = tree {
+            let left = len(&node.left);
+            if k <= left {
+                tree = &node.left;
+            } else {
+                total += sum(&node.left) + node.value;
+                k -= left + 1;
+                tree = &node.right;
+            }
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn matches_a_sorted_map() {
+        let mut ranked = RankedMap::new();
+        let mut model = BTreeMap::new();
+        // A walk through keys that inserts, updates and removes
+        let mut x = 1u64;
+        for step in 0..2000u64 {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let key = (x >> 33) % 100;
+            if step % 3 == 0 {
+                assert_eq!(ranked.remove(&key), model.remove(&key));
+            } else {
+                assert_eq!(ranked.insert(key, step), model.insert(key, step));
+            }
+        }
+
+        assert_eq!(ranked.len(), model.len());
+        assert_eq!(ranked.total(), model.values().sum::<u64>());
+        for (rank, (key, &value)) in model.iter().enumerate() {
+            assert_eq!(ranked.rank(key), Some(rank));
+            assert_eq!(ranked.nth(rank), Some((key, value)));
+            assert_eq!(ranked.get(key), Some(value));
+            let first = model.values().take(rank).sum::<u64>();
+            assert_eq!(ranked.sum_first(rank), first);
+        }
+        assert_eq!(ranked.nth(model.len()), None);
+        assert_eq!(ranked.sum_first(usize::MAX), ranked.total());
+        assert_eq!(ranked.rank(&1000), None);
+    }
+}
@@ -0,0 +1,312 @@
+//! A status page of all days.
+//!
+//! The runner prints a [`PartReport`] line for every part when it's given
+//! `--summary`, including the parts that have no implementation. The `report`
+//! binary collects those lines from every day into a [`Report`] and renders
+//! it as a Markdown or HTML table.
+
+use std::{fmt::Write, time::Duration};
+
+use anyhow::{bail, Context};
+
+/// Marks the summary lines among everything else a day prints.
+const PREFIX: &str = "aoc-summary";
+
+/// How a part went.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    Solved(String),
+    Failed(String),
+    TimedOut(String),
+    /// The day doesn't register an implementation of the part
+    Missing,
+}
+
+/// The outcome of one part of one day.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartReport {
+    pub day: u32,
+    pub part: usize,
+    pub implementation: Option<String>,
+    pub status: Status,
+    pub elapsed: Option<Duration>,
+}
+
+impl PartReport {
+    /// Encodes the report as one tab separated summary line.
+    pub fn to_line(&self) -> String {
+        let (status, text) = match &self.status {
+            Status::Solved(answer) => ("ok", answer.as_str()),
+            Status::Failed(msg) => ("failed", msg.as_str()),
+            Status::TimedOut(msg) => ("timeout", msg.as_str()),
+            Status::Missing => ("missing", ""),
+        };
+        let elapsed = self
+            .elapsed
+            .map_or("-".to_string(), |e| e.as_nanos().to_string());
+        format!(
+            "{PREFIX}\t{}\t{}\t{}\t{status}\t{elapsed}\t{}",
+            self.day,
+            self.part,
+            self.implementation.as_deref().map_or("-".into(), escape),
+            escape(text)
+        )
+    }
+
+    /// Decodes a summary line, or returns `None` for any other line.
+    pub fn from_line(line: &str) -> Option<anyhow::Result<PartReport>> {
+        let fields = line.strip_prefix(PREFIX)?.strip_prefix('\t')?;
+        Some(Self::parse_fields(fields).with_context(|| format!("invalid summary line: {line}")))
+    }
+
+    fn parse_fields(fields: &str) -> anyhow::Result<PartReport> {
+        let fields = fields.splitn(6, '\t').collect::<Vec<_>>();
+        let [day, part, implementation, status, elapsed, text] = fields[..] else {
+            bail!("expected 6 fields");
+        };
+        let text = unescape(text);
+        let status = match status {
+            "ok" => Status::Solved(text),
+            "failed" => Status::Failed(text),
+            "timeout" => Status::TimedOut(text),
+            "missing" => Status::Missing,
+            _ => bail!("unknown status: {status}"),
+        };
+        Ok(PartReport {
+            day: day.parse()?,
+            part: part.parse()?,
+            implementation: (implementation != "-").then(|| unescape(implementation)),
+            status,
+            elapsed: match elapsed {
+                "-" => None,
+                ns => Some(Duration::from_nanos(ns.parse()?)),
+            },
+        })
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => out.push('\n'),
+            ('\\', Some('t')) => out.push('\t'),
+            ('\\', Some('\\')) => out.push('\\'),
+            _ => {
+                out.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+    out
+}
+
+/// The parts of all days, in the order they ran.
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub parts: Vec<PartReport>,
+    /// Replace the answers with a placeholder, to share the page without
+    /// spoiling the puzzles
+    pub mask: bool,
+}
+
+impl Report {
+    fn solved(&self) -> usize {
+        self.parts
+            .iter()
+            .filter(|p| matches!(p.status, Status::Solved(_)))
+            .count()
+    }
+
+    /// Returns the text of the answer column; pictures, i.e. multiline
+    /// answers, are listed below the table instead.
+    fn answer(&self, part: &PartReport) -> Option<String> {
+        match &part.status {
+            Status::Solved(_) if self.mask => Some("•••".to_string()),
+            Status::Solved(answer) if answer.contains('\n') => Some("picture, see below".into()),
+            Status::Solved(answer) => Some(answer.clone()),
+            Status::Failed(msg) | Status::TimedOut(msg) => Some(msg.clone()),
+            Status::Missing => None,
+        }
+    }
+
+    fn pictures(&self) -> impl Iterator<Item = (&PartReport, &str)> {
+        self.parts.iter().filter_map(move |p| match &p.status {
+            Status::Solved(answer) if !self.mask && answer.contains('\n') => {
+                Some((p, answer.as_str()))
+            }
+            _ => None,
+        })
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "# Advent of Code 2022\n").unwrap();
+        writeln!(
+            out,
+            "{} of {} parts solved.\n",
+            self.solved(),
+            self.parts.len()
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "| Day | Part | Status | Answer | Time | Implementation |"
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "|----:|-----:|--------|--------|-----:|----------------|"
+        )
+        .unwrap();
+        for part in &self.parts {
+            let status = match part.status {
+                Status::Solved(_) => "solved",
+                Status::Failed(_) => "**failed**",
+                Status::TimedOut(_) => "**timed out**",
+                Status::Missing => "**missing**",
+            };
+            let answer = match (&part.status, self.answer(part)) {
+                (Status::Solved(_), Some(answer)) if !answer.contains(' ') => format!("`{answer}`"),
+                (_, answer) => answer.unwrap_or_default().replace('|', "\\|"),
+            };
+            writeln!(
+                out,
+                "| {} | {} | {status} | {answer} | {} | {} |",
+                part.day,
+                part.part,
+                part.elapsed.map_or(String::new(), |e| format!("{e:.2?}")),
+                part.implementation.as_deref().unwrap_or_default(),
+            )
+            .unwrap();
+        }
+        for (part, picture) in self.pictures() {
+            writeln!(out, "\n## Day {}, part {}\n", part.day, part.part).unwrap();
+            writeln!(out, "```\n{picture}\n```").unwrap();
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        out.push_str(concat!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n",
+            "<title>Advent of Code 2022</title>\n<style>\n",
+            "table { border-collapse: collapse; }\n",
+            "td, th { border: 1px solid #ccc; padding: 2px 8px; }\n",
+            ".solved { color: green; } .failed, .missing, .timeout { color: red; }\n",
+            "</style>\n</head>\n<body>\n<h1>Advent of Code 2022</h1>\n"
+        ));
+        writeln!(
+            out,
+            "<p>{} of {} parts solved.</p>",
+            self.solved(),
+            self.parts.len()
+        )
+        .unwrap();
+        out.push_str("<table>\n<tr><th>Day</th><th>Part</th><th>Status</th><th>Answer</th><th>Time</th><th>Implementation</th></tr>\n");
+        for part in &self.parts {
+            let (class, status) = match part.status {
+                Status::Solved(_) => ("solved", "solved"),
+                Status::Failed(_) => ("failed", "failed"),
+                Status::TimedOut(_) => ("timeout", "timed out"),
+                Status::Missing => ("missing", "missing"),
+            };
+            let answer = match &part.status {
+                Status::Solved(answer) if !self.mask && answer.contains('\n') => {
+                    format!("<pre>{}</pre>", html_escape(answer))
+                }
+                _ => html_escape(&self.answer(part).unwrap_or_default()),
+            };
+            writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td class=\"{class}\">{status}</td><td>{answer}</td><td>{}</td><td>{}</td></tr>",
+                part.day,
+                part.part,
+                part.elapsed.map_or(String::new(), |e| format!("{e:.2?}")),
+                html_escape(part.implementation.as_deref().unwrap_or_default()),
+            )
+            .unwrap();
+        }
+        out.push_str("</table>\n</body>\n</html>\n");
+        out
+    }
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        let part = |day, part, status| PartReport {
+            day,
+            part,
+            implementation: (status != Status::Missing).then(|| "scan".to_string()),
+            elapsed: (status != Status::Missing).then(|| Duration::from_millis(3)),
+            status,
+        };
+        Report {
+            parts: vec![
+                part(1, 1, Status::Solved("69177".into())),
+                part(10, 2, Status::Solved("#.\n.#".into())),
+                part(11, 1, Status::Missing),
+                part(23, 1, Status::Failed("not solved yet".into())),
+            ],
+            mask: false,
+        }
+    }
+
+    #[test]
+    fn summary_line_roundtrip() {
+        for part in report().parts {
+            let line = part.to_line();
+            assert_eq!(line.lines().count(), 1);
+            assert_eq!(PartReport::from_line(&line).unwrap().unwrap(), part);
+        }
+        assert!(PartReport::from_line("Part 1: 3").is_none());
+        assert!(PartReport::from_line("aoc-summary\t1\t1").unwrap().is_err());
+        assert_eq!(unescape(&escape("a\\nb\tc\n")), "a\\nb\tc\n");
+    }
+
+    #[test]
+    fn flags_missing_parts() {
+        let md = report().to_markdown();
+        assert!(md.contains("2 of 4 parts solved"));
+        assert!(md.contains("| 1 | 1 | solved | `69177` | 3.00ms | scan |"));
+        assert!(md.contains("| 11 | 1 | **missing** |  |  |  |"));
+        assert!(md.contains("| 23 | 1 | **failed** | not solved yet |"));
+        assert!(md.contains("## Day 10, part 2\n\n```\n#.\n.#\n```"));
+
+        let html = report().to_html();
+        assert!(html.contains("<td class=\"missing\">missing</td>"));
+        assert!(html.contains("<pre>#.\n.#</pre>"));
+    }
+
+    #[test]
+    fn masks_answers() {
+        let report = Report {
+            mask: true,
+            ..report()
+        };
+        let md = report.to_markdown();
+        assert!(!md.contains("69177"));
+        assert!(!md.contains("#.\n.#"));
+        assert!(md.contains("not solved yet"));
+        assert!(!report.to_html().contains("69177"));
+    }
+}
@@ -0,0 +1,505 @@
+//! Command line runner for a [`Day`].
+//!
+//! ```text
+//! cargo run --example=dayN -- [OPTIONS]
+//!
+//!     --list          list the implementations of each part and the assumptions
+//!                     about the input
+//!     --impl NAME     run the implementation NAME of each part that has one
+//!     --all           run every implementation of each part, but those that
+//!                     only run on request
+//!     --check         run the implementations that --all runs and fail if
+//!                     their answers differ
+//!     --bench RUNS    run the selected implementations RUNS times and report
+//!                     their timings
+//!     --input PATH    read the puzzle input from PATH instead of the embedded one
+//!     --budget SECS   cancel each implementation that runs longer than SECS
+//!                     seconds; a timed out part is reported but doesn't fail
+//!                     the run
+//!     --checkpoint DIR
+//!                     let the implementations that support it save their
+//!                     progress in DIR every minute and when they are
+//!                     cancelled or interrupted with Ctrl-C, and resume from
+//!                     there on the next run
+//!     --summary       print one machine readable line per part instead of the
+//!                     answers, for the `report` binary
+//!     --batch DIR     run on every input file in DIR in parallel and print a
+//!                     table of the results, see [`crate::batch`]
+//!     --MODE [VALUE]  print the report of one of the day's modes on the input
+//!                     instead of solving the parts, see [`Day::mode`]; `--list`
+//!                     lists them
+//! ```
+
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{bail, Context};
+
+use crate::{
+    batch,
+    cancel::{self, Token},
+    checkpoint::{self, Checkpointer},
+    day::{Answer, Day, Solver},
+    phase,
+    report::{PartReport, Status},
+};
+
+/// The options of a run, usually parsed from the command line.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    pub list: bool,
+    pub implementation: Option<String>,
+    pub all: bool,
+    pub check: bool,
+    pub bench: Option<usize>,
+    pub input: Option<PathBuf>,
+    pub budget: Option<Duration>,
+    pub checkpoint: Option<PathBuf>,
+    pub summary: bool,
+    pub batch: Option<PathBuf>,
+    /// The name of the day's mode to run instead of the parts, and its value
+    pub mode: Option<(String, Option<String>)>,
+}
+
+/// How often a solver saves its progress with `--checkpoint`.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60);
+
+impl Options {
+    /// Parses the options that every day takes.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Options> {
+        Self::parse_with(args, |_| None)
+    }
+
+    /// Parses the options that every day takes and the modes of `day`.
+    pub fn parse_for<I>(
+        day: &Day<I>,
+        args: impl IntoIterator<Item = String>,
+    ) -> anyhow::Result<Options> {
+        Self::parse_with(args, |name| {
+            day.find_mode(name).map(|mode| mode.value().is_some())
+        })
+    }
+
+    /// `takes_value` says whether the mode of a name takes a value, if the
+    /// day has that mode.
+    fn parse_with(
+        args: impl IntoIterator<Item = String>,
+        takes_value: impl Fn(&str) -> Option<bool>,
+    ) -> anyhow::Result<Options> {
+        let mut opts = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .with_context(|| format!("{arg} expects a value"))
+            };
+            match arg.as_str() {
+                "--list" => opts.list = true,
+                "--impl" => opts.implementation = Some(value()?),
+                "--all" => opts.all = true,
+                "--check" => opts.check = true,
+                "--bench" => {
+                    let runs = value()?;
+                    let runs = runs
+                        .parse()
+                        .with_context(|| format!("invalid number of runs: {runs}"))?;
+                    opts.bench = Some(runs)
+                }
+                "--input" => opts.input = Some(value()?.into()),
+                "--budget" => {
+                    let secs = value()?;
+                    let secs = secs
+                        .parse()
+                        .ok()
+                        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                        .with_context(|| format!("invalid budget: {secs}"))?;
+                    opts.budget = Some(secs)
+                }
+                "--checkpoint" => opts.checkpoint = Some(value()?.into()),
+                "--summary" => opts.summary = true,
+                "--batch" => opts.batch = Some(value()?.into()),
+                _ => {
+                    let Some((name, takes_value)) = arg
+                        .strip_prefix("--")
+                        .and_then(|name| Some((name, takes_value(name)?)))
+                    else {
+                        bail!("unknown argument: {arg}");
+                    };
+                    if opts.mode.is_some() {
+                        bail!("{arg}: only one mode can run at a time");
+                    }
+                    let value = if takes_value { Some(value()?) } else { None };
+                    opts.mode = Some((name.to_string(), value));
+                }
+            }
+        }
+        Ok(opts)
+    }
+}
+
+/// Removes the option `name` and its value from `args`, returning the value.
+/// This is for the options a day needs before it can build its [`Day`],
+/// like the rules of day 2; the rest of `args` then goes to
+/// [`Options::parse_for`].
+pub fn take_value(args: &mut Vec<String>, name: &str) -> anyhow::Result<Option<String>> {
+    let Some(idx) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    let value = args
+        .get(idx + 1)
+        .with_context(|| format!("{name} expects a value"))?
+        .clone();
+    args.drain(idx..idx + 2);
+    Ok(Some(value))
+}
+
+/// Runs `day` with the options given on the command line.
+pub fn main<I>(day: &Day<I>) -> anyhow::Result<()> {
+    let opts = Options::parse_for(day, std::env::args().skip(1))?;
+    run(day, &opts)
+}
+
+/// Runs `day` with `opts`, printing the answers on stdout and the
+/// measurements on stderr.
+pub fn run<I>(day: &Day<I>, opts: &Options) -> anyhow::Result<()> {
+    if opts.list {
+        for part in 1..=2 {
+            let names = day
+                .solvers(part)
+                .iter()
+                .map(|s| match s.on_request() {
+                    true => format!("{} (on request)", s.name()),
+                    false => s.name().to_string(),
+                })
+                .collect::<Vec<_>>();
+            println!("Part {part}: {}", names.join(", "));
+        }
+        for assumption in day.assumptions() {
+            println!("Assumes {assumption}");
+        }
+        for mode in day.modes() {
+            println!("Mode {mode}");
+        }
+        return Ok(());
+    }
+    cancel::cancel_on_interrupt()?;
+    if let Some(dir) = &opts.batch {
+        return batch::run(day, dir, opts);
+    }
+
+    let text = match &opts.input {
+        Some(path) => fs_err::read_to_string(path)?,
+        None => day.input().to_string(),
+    };
+    let input = phase::phase("parse", || day.parse(&text))?;
+    if let Some((name, value)) = &opts.mode {
+        let mode = day
+            .find_mode(name)
+            .with_context(|| format!("day {} has no mode named \"{name}\"", day.number()))?;
+        let report = mode.report(&input, value.as_deref())?;
+        print!("{report}");
+        if !report.ends_with('\n') {
+            println!();
+        }
+        return Ok(());
+    }
+    if let Some(dir) = &opts.checkpoint {
+        fs_err::create_dir_all(dir)?;
+    }
+
+    // Report the missing parts first, so they are known even if a solver
+    // crashes
+    if opts.summary {
+        for part in (1..=2).filter(|&part| day.solvers(part).is_empty()) {
+            let report = PartReport {
+                day: day.number(),
+                part,
+                implementation: None,
+                status: Status::Missing,
+                elapsed: None,
+            };
+            println!("{}", report.to_line());
+        }
+    }
+
+    let mut failures = vec![];
+    for part in 1..=2 {
+        let solvers = select(day, part, opts)?;
+        let show_name = solvers.len() > 1 || opts.implementation.is_some();
+
+        let mut answers = vec![];
+        for solver in solvers {
+            let label = format!("part {part} ({})", solver.name());
+            let (answer, elapsed) = match opts.bench {
+                Some(runs) => bench(&label, day, part, solver, &input, runs, opts),
+                None => {
+                    let (answer, report) =
+                        phase::measure(|| solve(day, part, solver, &input, opts));
+                    eprintln!("[{label}] {report}");
+                    (answer, report.elapsed)
+                }
+            };
+
+            if opts.summary {
+                let status = match &answer {
+                    Ok(answer) => Status::Solved(answer.clone()),
+                    Err(e) if cancel::is_timed_out(e) => Status::TimedOut(format!("{e:#}")),
+                    Err(e) => Status::Failed(format!("{e:#}")),
+                };
+                let report = PartReport {
+                    day: day.number(),
+                    part,
+                    implementation: Some(solver.name().to_string()),
+                    status,
+                    elapsed: Some(elapsed),
+                };
+                println!("{}", report.to_line());
+            }
+
+            let title = if show_name {
+                format!("Part {part} ({})", solver.name())
+            } else {
+                format!("Part {part}")
+            };
+            match &answer {
+                _ if opts.summary => {}
+                Ok(answer) if answer.contains('\n') => println!("{title}:\n{answer}"),
+                Ok(answer) => println!("{title}: {answer}"),
+                Err(e) if cancel::is_timed_out(e) => println!("{title} {e:#}"),
+                Err(e) => println!("{title} failed: {e:#}"),
+            }
+            if answer.as_ref().is_err_and(|e| !cancel::is_timed_out(e)) {
+                failures.push(format!("part {part} ({})", solver.name()));
+            }
+            answers.push((solver.name(), answer));
+        }
+
+        if opts.check {
+            let mut ok = answers
+                .iter()
+                .filter_map(|(name, a)| Some((name, a.as_ref().ok()?)));
+            if let Some((first_name, first)) = ok.next() {
+                for (name, answer) in ok {
+                    if answer != first {
+                        println!("Part {part}: {name} disagrees with {first_name}");
+                        failures.push(format!("part {part} ({name} vs. {first_name})"));
+                    }
+                }
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!("day {} failed: {}", day.number(), failures.join(", "));
+    }
+    Ok(())
+}
+
+/// Returns the implementations of `part` that `opts` asks for.
+pub(crate) fn select<'d, I>(
+    day: &'d Day<I>,
+    part: usize,
+    opts: &Options,
+) -> anyhow::Result<Vec<&'d Solver<I>>> {
+    let solvers = day.solvers(part);
+    if opts.all || opts.check {
+        return Ok(solvers.iter().filter(|s| !s.on_request()).collect());
+    }
+    if let Some(name) = &opts.implementation {
+        if (1..=2).all(|part| day.solver(part, name).is_none()) {
+            bail!(
+                "day {} has no implementation named \"{name}\"",
+                day.number()
+            );
+        }
+        // Parts without an implementation of that name fall back to the default
+        if let Some(solver) = day.solver(part, name) {
+            return Ok(vec![solver]);
+        }
+    }
+    Ok(solvers.iter().take(1).collect())
+}
+
+/// Runs `solver` on `input` within the time budget of `opts`, with a
+/// checkpointer if `opts` asks for one.
+pub(crate) fn solve<I>(
+    day: &Day<I>,
+    part: usize,
+    solver: &Solver<I>,
+    input: &I,
+    opts: &Options,
+) -> Answer {
+    let token = opts.budget.map_or_else(Token::new, Token::with_budget);
+    let checkpointer = opts.checkpoint.as_ref().map(|dir| {
+        let name = solver.name().replace(char::is_whitespace, "-");
+        let path = dir.join(format!("day{}-part{part}-{name}.txt", day.number()));
+        Checkpointer::new(path, CHECKPOINT_INTERVAL)
+    });
+    cancel::scoped(&token, || {
+        checkpoint::scoped(checkpointer.as_ref(), || solver.solve(input))
+    })
+}
+
+/// Runs `solver` `runs` times and reports the spread of its timings. Returns
+/// the answer of the last run and the median time.
+fn bench<I>(
+    label: &str,
+    day: &Day<I>,
+    part: usize,
+    solver: &Solver<I>,
+    input: &I,
+    runs: usize,
+    opts: &Options,
+) -> (Answer, Duration) {
+    let mut times = Vec::with_capacity(runs);
+    let mut answer = Ok(String::new());
+    for _ in 0..runs.max(1) {
+        let (ret, report) = phase::measure(|| solve(day, part, solver, input, opts));
+        times.push(report.elapsed);
+        answer = ret;
+    }
+    times.sort();
+
+    let mean = times.iter().sum::<Duration>() / times.len() as u32;
+    eprintln!(
+        "[{label}] {} runs: min {:.2?}, median {:.2?}, mean {:.2?}, max {:.2?}",
+        times.len(),
+        times[0],
+        times[times.len() / 2],
+        mean,
+        times[times.len() - 1]
+    );
+    (answer, times[times.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_options() {
+        let opts = Options::parse(args(
+            "--impl greedy --bench 5 --input in.txt --budget 1.5 --checkpoint ckpt --batch inputs",
+        ))
+        .unwrap();
+        assert_eq!(opts.implementation.as_deref(), Some("greedy"));
+        assert_eq!(opts.bench, Some(5));
+        assert_eq!(opts.input, Some(PathBuf::from("in.txt")));
+        assert_eq!(opts.budget, Some(Duration::from_millis(1500)));
+        assert_eq!(opts.checkpoint, Some(PathBuf::from("ckpt")));
+        assert_eq!(opts.batch, Some(PathBuf::from("inputs")));
+        assert!(!opts.all);
+
+        assert!(Options::parse(args("--bench")).is_err());
+        assert!(Options::parse(args("--bench x")).is_err());
+        assert!(Options::parse(args("--frobnicate")).is_err());
+        assert!(Options::parse(args("--budget -1")).is_err());
+    }
+
+    #[test]
+    fn takes_values_out_of_args() {
+        let mut rest = args("--all --rules rules.txt --input in.txt");
+        let rules = take_value(&mut rest, "--rules").unwrap();
+        assert_eq!(rules.as_deref(), Some("rules.txt"));
+        assert_eq!(rest, args("--all --input in.txt"));
+        assert_eq!(take_value(&mut rest, "--rules").unwrap(), None);
+        assert!(take_value(&mut args("--all --rules"), "--rules").is_err());
+    }
+
+    #[test]
+    fn parses_the_modes_of_the_day() {
+        let day = Day::new(0, "3", |input| Ok(input.parse::<u32>()?))
+            .part1("double", |x| x * 2)
+            .mode("scale", Some("FACTOR"), "scale the number", |x, factor| {
+                Ok((x * factor.unwrap().parse::<u32>()?).to_string())
+            })
+            .mode("digits", None, "count the digits", |x, _| {
+                Ok(x.to_string().len().to_string())
+            });
+
+        let opts = Options::parse_for(&day, args("--scale 4 --input in.txt")).unwrap();
+        assert_eq!(
+            opts.mode,
+            Some(("scale".to_string(), Some("4".to_string())))
+        );
+        assert_eq!(opts.input, Some(PathBuf::from("in.txt")));
+        let opts = Options::parse_for(&day, args("--digits")).unwrap();
+        assert_eq!(opts.mode, Some(("digits".to_string(), None)));
+        assert!(run(&day, &opts).is_ok());
+
+        assert!(Options::parse_for(&day, args("--scale")).is_err());
+        assert!(Options::parse_for(&day, args("--digits --scale 2")).is_err());
+        assert!(Options::parse_for(&day, args("--double")).is_err());
+        assert!(Options::parse(args("--digits")).is_err());
+
+        let opts = Options::parse_for(&day, args("--scale x")).unwrap();
+        assert!(run(&day, &opts).is_err());
+    }
+
+    #[test]
+    fn timeout_does_not_fail_the_run() {
+        let day = Day::new(0, "", |_| Ok(())).part1("spin", |_| -> anyhow::Result<u32> {
+            loop {
+                cancel::check(|| "still spinning")?;
+            }
+        });
+        let opts = Options {
+            budget: Some(Duration::from_millis(10)),
+            ..Options::default()
+        };
+        assert!(run(&day, &opts).is_ok());
+    }
+
+    #[test]
+    fn check_fails_on_disagreement() {
+        let day = Day::new(0, "3", |input| Ok(input.parse::<u32>()?))
+            .part1("double", |x| x * 2)
+            .part1("square", |x| x * x);
+        let opts = Options {
+            check: true,
+            ..Options::default()
+        };
+        assert!(run(&day, &opts).is_err());
+
+        let day = Day::new(0, "2", |input| Ok(input.parse::<u32>()?))
+            .part1("double", |x| x * 2)
+            .part1("square", |x| x * x);
+        assert!(run(&day, &opts).is_ok());
+    }
+
+    #[test]
+    fn runs_slow_implementations_only_on_request() {
+        let day = Day::new(0, "3", |input| Ok(input.parse::<u32>()?))
+            .part1("double", |x| x * 2)
+            .part1("square", |x| x * x)
+            .on_request();
+        let opts = Options {
+            check: true,
+            ..Options::default()
+        };
+        assert!(run(&day, &opts).is_ok());
+        let names = |opts| {
+            let solvers = select(&day, 1, &opts).unwrap();
+            solvers.iter().map(|s| s.name()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(opts), ["double"]);
+        let opts = Options {
+            implementation: Some("square".to_string()),
+            ..Options::default()
+        };
+        assert_eq!(names(opts), ["square"]);
+    }
+
+    #[test]
+    fn unknown_implementation_is_an_error() {
+        let day = Day::new(0, "2", |input| Ok(input.parse::<u32>()?)).part1("double", |x| x * 2);
+        let opts = Options {
+            implementation: Some("triple".to_string()),
+            ..Options::default()
+        };
+        assert!(run(&day, &opts).is_err());
+    }
+}
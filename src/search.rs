@@ -0,0 +1,221 @@
+//! Branch and bound over the states of a game.
+//!
+//! A [`Search`] looks for the state with the highest value among those
+//! reachable from a start state. It explores them depth first, in the order
+//! the move generator returns them, and skips every state whose upper bound
+//! can't beat the best value found so far. With [`Search::dominance`] it also
+//! skips a state when an earlier state with the same key was at least as
+//! good. The [`SearchStats`] of a run tell how much each of these saved.
+
+use std::{collections::HashMap, fmt, hash::Hash};
+
+use crate::cancel;
+
+/// How often the search polls for cancellation, in nodes
+const CANCEL_INTERVAL: usize = 1 << 12;
+
+/// Counters of a [`Search`] run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// States whose moves were generated
+    pub expanded: usize,
+    /// States skipped because their bound was no better than the best value
+    pub bounded: usize,
+    /// States skipped because an earlier state dominated them
+    pub dominated: usize,
+    /// Times a better state was found
+    pub improvements: usize,
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} expanded, {} bounded, {} dominated, {} improvements",
+            self.expanded, self.bounded, self.dominated, self.improvements
+        )
+    }
+}
+
+/// The best state of a [`Search`] run and its value.
+#[derive(Clone, Debug)]
+pub struct Outcome<S, V> {
+    pub best: S,
+    pub value: V,
+    pub stats: SearchStats,
+    /// Whether the run was cancelled, so that `best` is only the best state
+    /// found before that
+    pub cancelled: bool,
+}
+
+type Branch<'a, S> = Box<dyn Fn(&S, &mut Vec<S>) + 'a>;
+type Dominates<'a, S> = Box<dyn Fn(&S, &S) -> bool + 'a>;
+
+struct Dominance<'a, S, K> {
+    key: Box<dyn Fn(&S) -> K + 'a>,
+    dominates: Dominates<'a, S>,
+}
+
+/// A branch and bound search that maximizes a value of type `V` over states
+/// of type `S`.
+pub struct Search<'a, S, V, K = ()> {
+    branch: Branch<'a, S>,
+    value: Box<dyn Fn(&S) -> V + 'a>,
+    bound: Box<dyn Fn(&S) -> V + 'a>,
+    dominance: Option<Dominance<'a, S, K>>,
+}
+
+impl<'a, S: Clone, V: Ord + Copy> Search<'a, S, V> {
+    /// Creates a search that gets the moves from a state with `branch`, which
+    /// pushes the next states onto the given vector, the value of stopping in
+    /// a state with `value`, and an upper bound of the values of a state and
+    /// all states after it with `bound`.
+    pub fn new(
+        branch: impl Fn(&S, &mut Vec<S>) + 'a,
+        value: impl Fn(&S) -> V + 'a,
+        bound: impl Fn(&S) -> V + 'a,
+    ) -> Self {
+        Search {
+            branch: Box::new(branch),
+            value: Box::new(value),
+            bound: Box::new(bound),
+            dominance: None,
+        }
+    }
+
+    /// Skips a state when `dominates(earlier, state)` holds for an earlier
+    /// state with the same `key`, that is when no state after `state` can be
+    /// better than the best state after `earlier`.
+    pub fn dominance<K: Hash + Eq>(
+        self,
+        key: impl Fn(&S) -> K + 'a,
+        dominates: impl Fn(&S, &S) -> bool + 'a,
+    ) -> Search<'a, S, V, K> {
+        Search {
+            branch: self.branch,
+            value: self.value,
+            bound: self.bound,
+            dominance: Some(Dominance {
+                key: Box::new(key),
+                dominates: Box::new(dominates),
+            }),
+        }
+    }
+}
+
+impl<S: Clone, V: Ord + Copy, K: Hash + Eq> Search<'_, S, V, K> {
+    /// Searches the states reachable from `start`. Stops early with the best
+    /// state so far and [`Outcome::cancelled`] set if the run is cancelled,
+    /// see [`crate::cancel`].
+    pub fn run(&self, start: S) -> Outcome<S, V> {
+        let mut stats = SearchStats::default();
+        let mut value = (self.value)(&start);
+        let mut best = start.clone();
+        // The undominated states seen so far, by key
+        let mut seen: HashMap<K, Vec<S>> = HashMap::new();
+
+        let mut stack = vec![start];
+        let mut moves = vec![];
+        let mut cancelled = false;
+        while let Some(state) = stack.pop() {
+            if stats.expanded.is_multiple_of(CANCEL_INTERVAL) && cancel::is_cancelled() {
+                cancelled = true;
+                break;
+            }
+            if (self.bound)(&state) <= value {
+                stats.bounded += 1;
+                continue;
+            }
+            if let Some(dominance) = &self.dominance {
+                let earlier = seen.entry((dominance.key)(&state)).or_default();
+                if earlier.iter().any(|e| (dominance.dominates)(e, &state)) {
+                    stats.dominated += 1;
+                    continue;
+                }
+                earlier.retain(|e| !(dominance.dominates)(&state, e));
+                earlier.push(state.clone());
+            }
+
+            let state_value = (self.value)(&state);
+            if state_value > value {
+                value = state_value;
+                best = state.clone();
+                stats.improvements += 1;
+            }
+
+            stats.expanded += 1;
+            (self.branch)(&state, &mut moves);
+            // Reversed, so that the first move is explored first
+            stack.extend(moves.drain(..).rev());
+        }
+
+        Outcome {
+            best,
+            value,
+            stats,
+            cancelled,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A knapsack: the items taken so far, their weight and their value
+    type Sack = (usize, u32, u32);
+
+    const ITEMS: [(u32, u32); 5] = [(5, 10), (4, 40), (6, 30), (3, 50), (2, 5)];
+    const CAPACITY: u32 = 10;
+
+    fn branch(&(next, weight, value): &Sack, moves: &mut Vec<Sack>) {
+        if let Some(&(w, v)) = ITEMS.get(next) {
+            if weight + w <= CAPACITY {
+                moves.push((next + 1, weight + w, value + v));
+            }
+            moves.push((next + 1, weight, value));
+        }
+    }
+
+    fn bound(&(next, _, value): &Sack) -> u32 {
+        value
+            + ITEMS[next.min(ITEMS.len())..]
+                .iter()
+                .map(|i| i.1)
+                .sum::<u32>()
+    }
+
+    #[test]
+    fn finds_the_best_state() {
+        let exhaustive = Search::new(branch, |s: &Sack| s.2, |_| u32::MAX).run((0, 0, 0));
+        assert_eq!((exhaustive.value, exhaustive.best.1), (95, 9));
+        assert_eq!(exhaustive.stats.bounded, 0);
+        assert!(!exhaustive.cancelled);
+
+        let bounded = Search::new(branch, |s: &Sack| s.2, bound).run((0, 0, 0));
+        assert_eq!(bounded.value, 95);
+        assert!(bounded.stats.bounded > 0);
+        assert!(bounded.stats.expanded < exhaustive.stats.expanded);
+    }
+
+    #[test]
+    fn reports_cancellation() {
+        let token = cancel::Token::new();
+        token.cancel();
+        let search = Search::new(branch, |s: &Sack| s.2, bound);
+        let outcome = cancel::scoped(&token, || search.run((0, 0, 0)));
+        assert!(outcome.cancelled);
+        assert_eq!((outcome.value, outcome.stats.expanded), (0, 0));
+    }
+
+    #[test]
+    fn skips_dominated_states() {
+        // A sack is no better than one that holds more value in less weight
+        // after looking at the same items
+        let search = Search::new(branch, |s: &Sack| s.2, |_| u32::MAX)
+            .dominance(|s| s.0, |a, b| a.1 <= b.1 && a.2 >= b.2);
+        let outcome = search.run((0, 0, 0));
+        assert_eq!(outcome.value, 95);
+        assert!(outcome.stats.dominated > 0);
+    }
+}
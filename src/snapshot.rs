@@ -0,0 +1,105 @@
+//! Snapshot tests for rendered text.
+//!
+//! A snapshot is the expected rendering stored as a text file next to the
+//! examples, in `examples/snapshots/`. [`assert_snapshot!`] compares a fresh
+//! rendering against it and fails with a line diff when they differ. Run the
+//! tests with `UPDATE_SNAPSHOTS=1` to write the current renderings as the new
+//! snapshots.
+//!
+//! [`assert_snapshot!`]: crate::assert_snapshot
+
+use std::{env, path::Path};
+
+/// Compares `actual` against the snapshot `name` in `examples/snapshots/`.
+#[macro_export]
+macro_rules! assert_snapshot {
+    ($name:expr, $actual:expr) => {
+        $crate::snapshot::assert_snapshot(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("examples/snapshots")
+                .join(format!("{}.txt", $name)),
+            &$actual,
+        )
+    };
+}
+
+/// Compares `actual` against the snapshot file at `path`, or overwrites the
+/// file when `UPDATE_SNAPSHOTS` is set.
+pub fn assert_snapshot(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        if let Some(dir) = path.parent() {
+            fs_err::create_dir_all(dir).unwrap();
+        }
+        fs_err::write(path, actual).unwrap();
+        return;
+    }
+
+    let expected = match fs_err::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) => panic!("{e}\nrun with UPDATE_SNAPSHOTS=1 to create the snapshot"),
+    };
+
+    if expected != actual {
+        panic!(
+            "snapshot {} does not match (- expected, + actual):\n{}",
+            path.display(),
+            diff_lines(&expected, actual)
+        );
+    }
+}
+
+/// Returns a line diff of `expected` and `actual`. Unchanged lines are
+/// prefixed with a space, removed lines with `-` and added lines with `+`.
+/// Lines end in `$` so that trailing whitespace is visible.
+pub fn diff_lines(expected: &str, actual: &str) -> String {
+    let a = expected.lines().collect::<Vec<_>>();
+    let b = actual.lines().collect::<Vec<_>>();
+
+    // Longest common subsequence lengths of every pair of suffixes
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push_str(&format!(" {}$\n", a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push_str(&format!("-{}$\n", a[i]));
+            i += 1;
+        } else {
+            out.push_str(&format!("+{}$\n", b[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_changed_line() {
+        let diff = diff_lines("#..\n.#.\n..#", "#..\n.##\n..#");
+        assert_eq!(diff, " #..$\n-.#.$\n+.##$\n ..#$\n");
+    }
+
+    #[test]
+    fn diffs_inserted_and_removed_lines() {
+        assert_eq!(diff_lines("a\nb", "a\nb\nc"), " a$\n b$\n+c$\n");
+        assert_eq!(diff_lines("a\nb\nc", "b\nc"), "-a$\n b$\n c$\n");
+    }
+}
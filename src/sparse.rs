@@ -0,0 +1,293 @@
+//! An unbounded grid of occupied cells.
+//!
+//! A [`SparseGrid`] stores a set of `(x, y)` positions, which may be
+//! negative, as bitmaps of 64×64 cells that are allocated when a cell in them
+//! is first set. It keeps its exact bounding box as cells come and go, and
+//! reads the eight neighbors of a cell with a few word lookups, which is what
+//! cellular automata like the elves of day 23 need.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+pub type Pos = (isize, isize);
+
+const CHUNK: isize = 64;
+
+/// The smallest rectangle holding all occupied cells, inclusive on both ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Pos,
+    pub max: Pos,
+}
+
+impl Bounds {
+    pub fn width(&self) -> usize {
+        self.max.0.abs_diff(self.min.0) + 1
+    }
+
+    pub fn height(&self) -> usize {
+        self.max.1.abs_diff(self.min.1) + 1
+    }
+
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+}
+
+/// The occupied cells in the 3×3 square around a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Neighborhood(u16);
+
+impl Neighborhood {
+    fn bit(dx: isize, dy: isize) -> u16 {
+        debug_assert!((-1..=1).contains(&dx) && (-1..=1).contains(&dy));
+        1 << ((dy + 1) * 3 + dx + 1)
+    }
+
+    /// Returns whether the cell at offset `(dx, dy)` is occupied, where both
+    /// offsets are between -1 and 1.
+    pub fn get(self, dx: isize, dy: isize) -> bool {
+        self.0 & Self::bit(dx, dy) != 0
+    }
+
+    /// Returns the number of occupied neighbors, not counting the center.
+    pub fn count(self) -> u32 {
+        (self.0 & !Self::bit(0, 0)).count_ones()
+    }
+}
+
+/// A set of cells on an unbounded plane.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SparseGrid {
+    /// One bit per cell, one word per row of a chunk
+    chunks: HashMap<Pos, [u64; CHUNK as usize]>,
+    /// The number of occupied cells in each row and column, to keep the
+    /// bounding box exact when cells are removed
+    rows: BTreeMap<isize, usize>,
+    cols: BTreeMap<isize, usize>,
+    len: usize,
+}
+
+fn split((x, y): Pos) -> (Pos, usize, usize) {
+    let chunk = (x.div_euclid(CHUNK), y.div_euclid(CHUNK));
+    (
+        chunk,
+        x.rem_euclid(CHUNK) as usize,
+        y.rem_euclid(CHUNK) as usize,
+    )
+}
+
+fn add(counts: &mut BTreeMap<isize, usize>, key: isize) {
+    *counts.entry(key).or_default() += 1;
+}
+
+fn sub(counts: &mut BTreeMap<isize, usize>, key: isize) {
+    let count = counts.get_mut(&key).expect("count of an occupied cell");
+    *count -= 1;
+    if *count == 0 {
+        counts.remove(&key);
+    }
+}
+
+impl SparseGrid {
+    pub fn new() -> SparseGrid {
+        SparseGrid::default()
+    }
+
+    /// Returns the number of occupied cells.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        let (chunk, x, y) = split(pos);
+        self.chunks
+            .get(&chunk)
+            .is_some_and(|rows| rows[y] >> x & 1 == 1)
+    }
+
+    /// Occupies `pos`. Returns whether it was free.
+    pub fn insert(&mut self, pos: Pos) -> bool {
+        let (chunk, x, y) = split(pos);
+        let row = &mut self.chunks.entry(chunk).or_insert([0; CHUNK as usize])[y];
+        if *row >> x & 1 == 1 {
+            return false;
+        }
+        *row |= 1 << x;
+        add(&mut self.cols, pos.0);
+        add(&mut self.rows, pos.1);
+        self.len += 1;
+        true
+    }
+
+    /// Frees `pos`. Returns whether it was occupied.
+    pub fn remove(&mut self, pos: Pos) -> bool {
+        let (chunk, x, y) = split(pos);
+        let Some(rows) = self.chunks.get_mut(&chunk) else {
+            return false;
+        };
+        if rows[y] >> x & 1 == 0 {
+            return false;
+        }
+        rows[y] &= !(1 << x);
+        if rows.iter().all(|&row| row == 0) {
+            self.chunks.remove(&chunk);
+        }
+        sub(&mut self.cols, pos.0);
+        sub(&mut self.rows, pos.1);
+        self.len -= 1;
+        true
+    }
+
+    /// Returns the bounding box of the occupied cells, or `None` if there are
+    /// none.
+    pub fn bounds(&self) -> Option<Bounds> {
+        let (&min_x, _) = self.cols.first_key_value()?;
+        let (&max_x, _) = self.cols.last_key_value()?;
+        let (&min_y, _) = self.rows.first_key_value()?;
+        let (&max_y, _) = self.rows.last_key_value()?;
+        Some(Bounds {
+            min: (min_x, min_y),
+            max: (max_x, max_y),
+        })
+    }
+
+    /// Returns the three cells from `(x - 1, y)` to `(x + 1, y)` as bits.
+    fn triple(&self, (x, y): Pos) -> u16 {
+        let (chunk, lx, ly) = split((x, y));
+        if (1..CHUNK as usize - 1).contains(&lx) {
+            // All three are in the same word
+            return self
+                .chunks
+                .get(&chunk)
+                .map_or(0, |rows| (rows[ly] >> (lx - 1) & 0b111) as u16);
+        }
+        (-1..=1)
+            .filter(|dx| self.contains((x + dx, y)))
+            .map(|dx| 1 << (dx + 1))
+            .sum()
+    }
+
+    /// Returns the occupied cells around `pos`, including `pos` itself.
+    pub fn neighborhood(&self, (x, y): Pos) -> Neighborhood {
+        let above = self.triple((x, y - 1));
+        let row = self.triple((x, y));
+        let below = self.triple((x, y + 1));
+        Neighborhood(above | row << 3 | below << 6)
+    }
+
+    /// Returns the number of occupied cells among the eight around `pos`.
+    pub fn count_neighbors(&self, pos: Pos) -> u32 {
+        self.neighborhood(pos).count()
+    }
+
+    /// Iterates over the occupied cells, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.chunks.iter().flat_map(|(&(cx, cy), rows)| {
+            rows.iter().enumerate().flat_map(move |(ly, &row)| {
+                let mut bits = row;
+                std::iter::from_fn(move || {
+                    if bits == 0 {
+                        return None;
+                    }
+                    let lx = bits.trailing_zeros() as isize;
+                    bits &= bits - 1;
+                    Some((cx * CHUNK + lx, cy * CHUNK + ly as isize))
+                })
+            })
+        })
+    }
+}
+
+impl FromIterator<Pos> for SparseGrid {
+    fn from_iter<T: IntoIterator<Item = Pos>>(iter: T) -> Self {
+        let mut grid = SparseGrid::new();
+        grid.extend(iter);
+        grid
+    }
+}
+
+impl Extend<Pos> for SparseGrid {
+    fn extend<T: IntoIterator<Item = Pos>>(&mut self, iter: T) {
+        for pos in iter {
+            self.insert(pos);
+        }
+    }
+}
+
+/// Draws the bounding box with `#` for occupied cells and `.` for free ones.
+impl fmt::Display for SparseGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(Bounds { min, max }) = self.bounds() else {
+            return Ok(());
+        };
+        for y in min.1..=max.1 {
+            if y > min.1 {
+                writeln!(f)?;
+            }
+            for x in min.0..=max.0 {
+                f.write_str(if self.contains((x, y)) { "#" } else { "." })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_exact_bounds() {
+        let mut grid: SparseGrid = [(0, 0), (1, 1), (2, 1)].into_iter().collect();
+        assert_eq!(grid.to_string(), "#..\n.##");
+        assert!(!grid.insert((1, 1)));
+
+        assert!(grid.insert((-70, -1)));
+        assert_eq!(
+            grid.bounds(),
+            Some(Bounds {
+                min: (-70, -1),
+                max: (2, 1)
+            })
+        );
+        assert!(grid.remove((-70, -1)));
+        assert!(!grid.remove((-70, -1)));
+        assert_eq!(grid.bounds().unwrap().area(), 6);
+        assert_eq!(grid.chunks.len(), 1);
+
+        let mut cells = grid.iter().collect::<Vec<_>>();
+        cells.sort();
+        assert_eq!(cells, [(0, 0), (1, 1), (2, 1)]);
+
+        for pos in cells {
+            grid.remove(pos);
+        }
+        assert!(grid.is_empty());
+        assert_eq!(grid.bounds(), None);
+    }
+
+    #[test]
+    fn counts_neighbors_across_chunks() {
+        // Around the origin, where four chunks meet
+        let grid: SparseGrid = [(-1, -1), (0, -1), (1, 0), (0, 1), (0, 0)]
+            .into_iter()
+            .collect();
+        let nb = grid.neighborhood((0, 0));
+        assert_eq!(nb.count(), 4);
+        assert!(nb.get(-1, -1) && nb.get(0, -1) && nb.get(1, 0) && nb.get(0, 1));
+        assert!(nb.get(0, 0) && !nb.get(1, 1) && !nb.get(-1, 0));
+        assert_eq!(grid.count_neighbors((5, 5)), 0);
+
+        // In the middle of a chunk
+        let grid: SparseGrid = [(9, 10), (11, 10), (10, 11)].into_iter().collect();
+        assert_eq!(grid.count_neighbors((10, 10)), 3);
+        assert!(grid.neighborhood((10, 10)).get(1, 0));
+    }
+}